@@ -1,5 +1,10 @@
 import React, { useState } from "react";
 import { useWasm } from "../hooks/useWasm";
+import type {
+  ComparatorReport,
+  SortAlgorithm,
+  SortStats,
+} from "../hooks/useWasm";
 import "./ArraySorter.css";
 
 type SortType = "numbers" | "strings" | "custom";
@@ -13,6 +18,11 @@ const ArraySorter: React.FC = () => {
   const [sortedArray, setSortedArray] = useState<unknown[]>([]);
   const [ascending, setAscending] = useState(true);
   const [sortTime, setSortTime] = useState<number | null>(null);
+  const [algorithm, setAlgorithm] = useState<SortAlgorithm>("auto");
+  const [stable, setStable] = useState(true);
+  const [sortStats, setSortStats] = useState<Partial<SortStats> | null>(null);
+  const [comparatorReport, setComparatorReport] =
+    useState<ComparatorReport | null>(null);
 
   const parseArray = (input: string): unknown[] => {
     try {
@@ -42,11 +52,49 @@ const ArraySorter: React.FC = () => {
     }
   };
 
+  // Custom predicate, or the default Array.prototype.sort order when empty
+  const buildPredicate = () =>
+    customPredicate.trim()
+      ? (new Function("a", "b", `return ${customPredicate}`) as (
+          a: unknown,
+          b: unknown
+        ) => number)
+      : undefined;
+
+  const handleValidate = () => {
+    const predicate = buildPredicate();
+    if (!wasm || !inputArray.trim() || !predicate) return;
+
+    try {
+      setComparatorReport(
+        wasm.validate_comparator(parseArray(inputArray), predicate)
+      );
+    } catch (err) {
+      console.error("Validation error:", err);
+      alert("Error while checking the predicate.");
+    }
+  };
+
   const handleSort = () => {
     if (!wasm || !inputArray.trim()) return;
 
     try {
       const parsedArray = parseArray(inputArray);
+      const stats: Partial<SortStats> = {};
+      // Radix sort only applies to numbers
+      const options: {
+        algorithm: SortAlgorithm;
+        stable?: boolean;
+        stats: Partial<SortStats>;
+      } = {
+        algorithm:
+          algorithm === "radix" && sortType !== "numbers" ? "auto" : algorithm,
+        stats,
+      };
+      // Only the standard library sort comes in both kinds
+      if (options.algorithm === "auto") {
+        options.stable = stable;
+      }
       const startTime = performance.now();
 
       let result: unknown[];
@@ -55,22 +103,17 @@ const ArraySorter: React.FC = () => {
         const numberArray = parsedArray
           .map((item) => Number(item))
           .filter((num) => !isNaN(num));
-        result = wasm.sort_numbers(numberArray, ascending);
+        result = wasm.sort_numbers(numberArray, ascending, options).values;
       } else if (sortType === "strings") {
         const stringArray = parsedArray.map((item) => String(item));
-        result = wasm.sort_strings(stringArray, ascending);
+        result = wasm.sort_strings(stringArray, ascending, options).values;
       } else {
-        // Custom predicate
-        const predicate = new Function(
-          "a",
-          "b",
-          `return ${customPredicate}`
-        ) as (a: unknown, b: unknown) => number;
-        result = wasm.sort_array(parsedArray, predicate);
+        result = wasm.sort_array(parsedArray, buildPredicate(), options);
       }
 
       const endTime = performance.now();
       setSortTime(endTime - startTime);
+      setSortStats(stats);
       setSortedArray(result);
     } catch (err) {
       console.error("Sorting error:", err);
@@ -220,9 +263,42 @@ const ArraySorter: React.FC = () => {
                 By Name: {examplePredicates.byName}
               </button>
             </div>
+            <button
+              onClick={handleValidate}
+              disabled={!inputArray.trim() || !customPredicate.trim()}
+            >
+              Check predicate consistency
+            </button>
           </div>
         )}
 
+        <div className="algorithm-selector">
+          <h3>Algorithm</h3>
+          <select
+            value={algorithm}
+            onChange={(e) => setAlgorithm(e.target.value as SortAlgorithm)}
+          >
+            <option value="auto">Auto</option>
+            <option value="insertion">Insertion sort</option>
+            <option value="merge">Merge sort</option>
+            <option value="heap">Heap sort</option>
+            <option value="quick">Quicksort</option>
+            <option value="pdq">Pattern-defeating quicksort</option>
+            <option value="timsort">Timsort</option>
+            {sortType === "numbers" && <option value="radix">Radix sort</option>}
+          </select>
+          {algorithm === "auto" && (
+            <label>
+              <input
+                type="checkbox"
+                checked={stable}
+                onChange={(e) => setStable(e.target.checked)}
+              />
+              Stable
+            </label>
+          )}
+        </div>
+
         <button
           className="sort-button"
           onClick={handleSort}
@@ -239,6 +315,31 @@ const ArraySorter: React.FC = () => {
             Sorted in {sortTime.toFixed(2)}ms using Rust WebAssembly
           </p>
         )}
+        {sortStats && (
+          <p className="sort-stats">
+            {sortStats.algorithm}: {sortStats.comparisons} comparisons,{" "}
+            {sortStats.moves ?? "n/a"} moves, {sortStats.callbacks} JS
+            callbacks, {sortStats.stable ? "stable" : "unstable"}
+          </p>
+        )}
+        {sortType === "custom" && comparatorReport && (
+          <div className="comparator-report">
+            {comparatorReport.valid ? (
+              <p>
+                Predicate is consistent on {comparatorReport.checkedPairs}{" "}
+                pairs and {comparatorReport.checkedTriples} triples
+              </p>
+            ) : (
+              <ul>
+                {comparatorReport.violations.map((violation, i) => (
+                  <li key={i}>
+                    {violation.kind}: {violation.message}
+                  </li>
+                ))}
+              </ul>
+            )}
+          </div>
+        )}
         {sortedArray.length > 0 && (
           <div className="sorted-array">
             <h4>Sorted Array:</h4>
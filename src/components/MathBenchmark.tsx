@@ -8,7 +8,9 @@ type BenchmarkType =
   | "primes"
   | "matrix"
   | "fibonacci"
-  | "hash";
+  | "hash"
+  | "radix"
+  | "heap";
 
 interface BenchmarkResult {
   wasmTime: number;
@@ -28,6 +30,7 @@ const MathBenchmark: React.FC = () => {
   const [mandelbrotSize, setMandelbrotSize] = useState<number>(200);
   const [fibonacciN, setFibonciN] = useState<number>(1000);
   const [hashIterations, setHashIterations] = useState<number>(100000);
+  const [sortSize, setSortSize] = useState<number>(1000000);
   const [result, setResult] = useState<BenchmarkResult | null>(null);
   const [running, setRunning] = useState(false);
 
@@ -167,6 +170,41 @@ const MathBenchmark: React.FC = () => {
     return hash >>> 0;
   };
 
+  // Array-backed binary min-heap: push every value, then pop them all
+  const jsHeapDrain = (values: number[]): number[] => {
+    const heap: number[] = [];
+    for (const value of values) {
+      let i = heap.push(value) - 1;
+      while (i > 0) {
+        const parent = (i - 1) >> 1;
+        if (heap[parent] <= heap[i]) break;
+        [heap[parent], heap[i]] = [heap[i], heap[parent]];
+        i = parent;
+      }
+    }
+
+    const drained: number[] = [];
+    while (heap.length > 0) {
+      drained.push(heap[0]);
+      const last = heap.pop()!;
+      if (heap.length === 0) break;
+      heap[0] = last;
+      let i = 0;
+      for (;;) {
+        const left = 2 * i + 1;
+        const right = left + 1;
+        let smallest = i;
+        if (left < heap.length && heap[left] < heap[smallest]) smallest = left;
+        if (right < heap.length && heap[right] < heap[smallest])
+          smallest = right;
+        if (smallest === i) break;
+        [heap[smallest], heap[i]] = [heap[i], heap[smallest]];
+        i = smallest;
+      }
+    }
+    return drained;
+  };
+
   const generateRandomMatrix = (rows: number, cols: number): number[] => {
     const matrix: number[] = [];
     for (let i = 0; i < rows * cols; i++) {
@@ -299,6 +337,47 @@ const MathBenchmark: React.FC = () => {
           break;
         }
 
+        case "radix": {
+          const values = generateRandomMatrix(sortSize, 1);
+
+          // WASM radix sort, in place on a typed array
+          const wasmValues = new Float64Array(values);
+          const wasmStart7 = performance.now();
+          wasm.sort_f64(wasmValues, true, { algorithm: "radix" });
+          wasmTime = performance.now() - wasmStart7;
+          wasmResult = wasmValues;
+
+          // JavaScript Array.prototype.sort
+          const jsStart7 = performance.now();
+          jsResult = values.sort((a, b) => a - b);
+          jsTime = performance.now() - jsStart7;
+          break;
+        }
+
+        case "heap": {
+          const values = generateRandomMatrix(sortSize, 1);
+
+          // WASM PriorityQueue, one call per push and pop
+          const wasmStart8 = performance.now();
+          const queue = new wasm.PriorityQueue<number>();
+          for (const value of values) {
+            queue.push(value, value);
+          }
+          const drained: number[] = [];
+          while (queue.length > 0) {
+            drained.push(queue.pop()!);
+          }
+          queue.free();
+          wasmTime = performance.now() - wasmStart8;
+          wasmResult = drained;
+
+          // JavaScript binary heap
+          const jsStart8 = performance.now();
+          jsResult = jsHeapDrain(values);
+          jsTime = performance.now() - jsStart8;
+          break;
+        }
+
         default:
           throw new Error("Unknown benchmark type");
       }
@@ -413,6 +492,28 @@ const MathBenchmark: React.FC = () => {
               />
               🔐 Hash Computation
             </label>
+            <label>
+              <input
+                type="radio"
+                value="radix"
+                checked={benchmarkType === "radix"}
+                onChange={(e) =>
+                  setBenchmarkType(e.target.value as BenchmarkType)
+                }
+              />
+              🗂️ Radix Sort
+            </label>
+            <label>
+              <input
+                type="radio"
+                value="heap"
+                checked={benchmarkType === "heap"}
+                onChange={(e) =>
+                  setBenchmarkType(e.target.value as BenchmarkType)
+                }
+              />
+              ⛰️ Priority Queue
+            </label>
           </div>
         </div>
 
@@ -506,6 +607,20 @@ const MathBenchmark: React.FC = () => {
               </select>
             </label>
           )}
+          {(benchmarkType === "radix" || benchmarkType === "heap") && (
+            <label>
+              Array Size:
+              <select
+                value={sortSize}
+                onChange={(e) => setSortSize(Number(e.target.value))}
+              >
+                <option value={100000}>100,000</option>
+                <option value={500000}>500,000</option>
+                <option value={1000000}>1,000,000</option>
+                <option value={5000000}>5,000,000</option>
+              </select>
+            </label>
+          )}
         </div>
 
         <button
@@ -1,15 +1,8 @@
-import React, { useState, useEffect } from "react";
+import React, { useState, useEffect, useMemo } from "react";
 import { useWasm } from "../hooks/useWasm";
+import type { Photo, SortSpec } from "../hooks/useWasm";
 import "./PhotoSorter.css";
 
-interface Photo {
-  albumId: number;
-  id: number;
-  title: string;
-  url: string;
-  thumbnailUrl: string;
-}
-
 type SortBy = "id" | "albumId" | "title" | "titleLength" | "custom";
 
 const PhotoSorter: React.FC = () => {
@@ -25,7 +18,9 @@ const PhotoSorter: React.FC = () => {
   );
   const [wasmSortTime, setWasmSortTime] = useState<number | null>(null);
   const [jsSortTime, setJsSortTime] = useState<number | null>(null);
+  const [jsonSortTime, setJsonSortTime] = useState<number | null>(null);
   const [limit, setLimit] = useState<number>(20);
+  const [shuffleSeed, setShuffleSeed] = useState<number>(42);
   const [performanceAnalysis, setPerformanceAnalysis] = useState<{
     dataSize: number;
     wasmOverhead: number;
@@ -84,6 +79,18 @@ const PhotoSorter: React.FC = () => {
     fetchPhotos();
   }, [limit]); // eslint-disable-line react-hooks/exhaustive-deps
 
+  // The photos as the JSON text the API sends, for the text-only mode
+  const photosJson = useMemo(() => JSON.stringify(photos), [photos]);
+
+  // The API returns photos sorted by id, which flatters algorithms that
+  // detect presorted runs; a seeded shuffle gives every run the same input
+  const shufflePhotos = () => {
+    if (!wasm || photos.length === 0) return;
+    const shuffled = wasm.shuffle(photos, shuffleSeed);
+    setPhotos(shuffled);
+    setSortedPhotos(shuffled);
+  };
+
   const getCompareFn = (): ((a: Photo, b: Photo) => number) => {
     switch (sortBy) {
       case "id":
@@ -111,12 +118,31 @@ const PhotoSorter: React.FC = () => {
     }
   };
 
+  // Key-path form of the current sort, for the modes that sort without a
+  // predicate. Title length and custom predicates have none.
+  const getSortSpec = (): SortSpec | null => {
+    const direction = ascending ? "asc" : "desc";
+    switch (sortBy) {
+      case "id":
+      case "albumId":
+        return { path: sortBy, direction };
+      case "title":
+        return { path: "title", type: "string", collation: "uca", direction };
+      default:
+        return null;
+    }
+  };
+
   const sortWithWasm = async (): Promise<{ result: Photo[]; time: number }> => {
     if (!wasm) throw new Error("WASM not loaded");
 
+    // Built-in fields are sorted on typed records entirely in Rust; only
+    // custom predicates still need the callback-driven path
     const startTime = performance.now();
-    const compareFn = getCompareFn();
-    const result = wasm.sort_array([...photos], compareFn);
+    const result =
+      sortBy === "custom"
+        ? wasm.sort_array([...photos], getCompareFn())
+        : wasm.sort_photos(photos, sortBy, ascending, { collation: "uca" });
     const endTime = performance.now();
 
     return { result, time: endTime - startTime };
@@ -131,14 +157,40 @@ const PhotoSorter: React.FC = () => {
     return { result, time: endTime - startTime };
   };
 
-  const handleSort = async (method: "wasm" | "js" | "both") => {
+  // Text in, text out: the photos never become JS objects on the Rust side.
+  // Parsing the result for display is left out of the measurement.
+  const sortWithJson = async (): Promise<{ result: Photo[]; time: number }> => {
+    if (!wasm) throw new Error("WASM not loaded");
+    const spec = getSortSpec();
+    if (!spec) throw new Error("This sort has no key-path form");
+
+    const startTime = performance.now();
+    const sorted = wasm.sort_json(photosJson, spec);
+    const endTime = performance.now();
+
+    return { result: JSON.parse(sorted), time: endTime - startTime };
+  };
+
+  const handleSort = async (method: "wasm" | "js" | "both" | "json") => {
     if (photos.length === 0) return;
 
     try {
       setWasmSortTime(null);
       setJsSortTime(null);
+      setJsonSortTime(null);
       setPerformanceAnalysis(null);
 
+      if (method === "json") {
+        if (!wasm) {
+          alert("WebAssembly module not loaded yet");
+          return;
+        }
+        const jsonResult = await sortWithJson();
+        setJsonSortTime(jsonResult.time);
+        setSortedPhotos(jsonResult.result);
+        return;
+      }
+
       let wasmTime: number | null = null;
       let jsTime: number | null = null;
 
@@ -214,9 +266,11 @@ const PhotoSorter: React.FC = () => {
         const factors = [];
 
         // Always present factors in our implementation
-        factors.push(
-          "🔄 WASM-JS boundary crossings: Every comparison function call crosses the boundary"
-        );
+        if (sortBy === "custom") {
+          factors.push(
+            "🔄 WASM-JS boundary crossings: Every comparison function call crosses the boundary"
+          );
+        }
         factors.push(
           "📦 Object serialization: Complex objects (Photos) require serialization between JS and WASM"
         );
@@ -339,6 +393,23 @@ const PhotoSorter: React.FC = () => {
                 : "Fetching..."
               : "Fetch Photos"}
           </button>
+          <div className="shuffle-control">
+            <label>
+              Seed:
+              <input
+                type="number"
+                min={0}
+                value={shuffleSeed}
+                onChange={(e) => setShuffleSeed(Number(e.target.value))}
+              />
+            </label>
+            <button
+              onClick={shufflePhotos}
+              disabled={loadingPhotos || photos.length === 0}
+            >
+              Shuffle Photos
+            </button>
+          </div>
           {photoError && <p className="error-message">{photoError}</p>}
           {limit > 5000 && (
             <p className="info-message">
@@ -427,6 +498,14 @@ const PhotoSorter: React.FC = () => {
             >
               🏁 Compare Both Methods
             </button>
+            <button
+              className="sort-button json-button"
+              onClick={() => handleSort("json")}
+              disabled={photos.length === 0 || !wasm || !getSortSpec()}
+              title="Sorts the JSON text in Rust, without JS objects or predicates"
+            >
+              📄 Sort JSON Text in Rust
+            </button>
           </div>
         </div>
       </div>
@@ -445,6 +524,11 @@ const PhotoSorter: React.FC = () => {
                 ⚡ JavaScript: {jsSortTime.toFixed(2)}ms
               </p>
             )}
+            {jsonSortTime !== null && (
+              <p className="sort-time json-time">
+                📄 Rust JSON text: {jsonSortTime.toFixed(2)}ms
+              </p>
+            )}
             {wasmSortTime !== null && jsSortTime !== null && (
               <p className="performance-comparison">
                 {wasmSortTime < jsSortTime ? (
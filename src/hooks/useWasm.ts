@@ -2,11 +2,365 @@ import { useState, useEffect } from "react";
 
 type SortPredicate<T> = (a: T, b: T) => number;
 
+export interface SortReport<T> {
+  values: T[];
+  rejectedCount: number;
+  rejected: Uint32Array;
+}
+
+export type SortAlgorithm =
+  | "auto"
+  | "comparison"
+  | "insertion"
+  | "merge"
+  | "heap"
+  | "quick"
+  | "pdq"
+  | "timsort"
+  | "radix";
+
+export interface SortStats {
+  algorithm: Exclude<SortAlgorithm, "auto">;
+  comparisons: number;
+  moves: number | null;
+  callbacks: number;
+  stable: boolean;
+  // Set when `sort_page` served a cached order and no sort ran
+  cached: boolean;
+}
+
+// Pass an empty object as `stats` to have it filled in after the sort
+export interface SortOptions {
+  algorithm?: SortAlgorithm;
+  stable?: boolean;
+  stats?: Partial<SortStats>;
+}
+
+export interface FloatOrderOptions {
+  nan?: "first" | "last";
+  signedZero?: boolean;
+}
+
+export interface FloatSortOptions extends FloatOrderOptions, SortOptions {}
+
+export interface NumberSortOptions extends FloatSortOptions {
+  coerce?: boolean;
+}
+
+export interface CollationOptions {
+  collation?: "binary" | "uca";
+  strength?: "base" | "accent" | "case";
+  // Natural order: runs of digits compare by value, so "Set 2" < "Set 10"
+  numeric?: boolean;
+}
+
+export interface StringSortOptions extends CollationOptions, SortOptions {
+  coerce?: boolean;
+}
+
+export type SortKeyType =
+  | "number"
+  | "string"
+  | "natural"
+  | "date"
+  | "bigint"
+  | "boolean"
+  | "mixed";
+
+export interface SortKeySpec extends CollationOptions, FloatOrderOptions {
+  path: string;
+  direction?: "asc" | "desc";
+  // Without a type, the first value found decides it
+  type?: SortKeyType;
+  nulls?: "first" | "last";
+}
+
+export interface SortArrayOptions extends SortOptions {
+  strict?: boolean;
+}
+
+export type ComparatorViolationKind =
+  | "reflexivity"
+  | "antisymmetry"
+  | "transitivity"
+  | "nonNumeric"
+  | "exception";
+
+export interface ComparatorViolation {
+  kind: ComparatorViolationKind;
+  indices: number[];
+  results: unknown[];
+  message: string;
+}
+
+export interface ComparatorReport {
+  valid: boolean;
+  seed: number;
+  checkedPairs: number;
+  checkedTriples: number;
+  counts: Record<ComparatorViolationKind, number>;
+  violations: ComparatorViolation[];
+}
+
+export interface ValidateComparatorOptions {
+  pairs?: number;
+  triples?: number;
+  maxViolations?: number;
+  seed?: number;
+}
+
+// Thrown by the expression APIs when an expression does not parse;
+// `position` and `length` locate the offending text in `expression`
+export interface ExpressionSyntaxError extends SyntaxError {
+  expression: string;
+  position: number;
+  length: number;
+}
+
+export type SortSpec = string | SortKeySpec | (string | SortKeySpec)[];
+
+// A JSONPlaceholder photo, mirrored by the `Photo` struct on the Rust side
+export interface Photo {
+  albumId: number;
+  id: number;
+  title: string;
+  url: string;
+  thumbnailUrl: string;
+}
+
+export type PhotoField = "id" | "albumId" | "title" | "titleLength";
+
+// `cache: false` re-sorts instead of reusing the permutation from the
+// previous call, e.g. after elements were changed in place
+export interface SortPageOptions extends SortOptions {
+  cache?: boolean;
+}
+
+export interface SortPage<T> {
+  items: T[];
+  total: number;
+  offset: number;
+}
+
+// Handles to WASM-side data structures must be released with `free()`
+export interface SortedCollection<T> {
+  readonly length: number;
+  insert: (item: T) => number;
+  insert_all: (items: T[]) => void;
+  remove: (item: T) => boolean;
+  remove_at: (index: number) => T | undefined;
+  get: (index: number) => T | undefined;
+  // Probes give a prefix of the spec's keys; skipping one throws
+  rank: (probe: Partial<T>) => number;
+  range: (lower?: Partial<T>, upper?: Partial<T>) => T[];
+  chunk: (start: number, count: number) => T[];
+  to_array: () => T[];
+  clear: () => void;
+  free: () => void;
+}
+
+interface SortedCollectionClass {
+  new <T>(spec: SortSpec): SortedCollection<T>;
+  from_array: <T>(array: T[], spec: SortSpec) => SortedCollection<T>;
+}
+
+export interface PriorityQueueOptions {
+  order?: "min" | "max";
+  key?: string;
+}
+
+export interface PriorityQueue<T> {
+  readonly length: number;
+  push: (priority: number, payload: T) => void;
+  push_item: (item: T) => void;
+  pop: () => T | undefined;
+  peek: () => T | undefined;
+  peek_priority: () => number | undefined;
+  drain_sorted: () => T[];
+  clear: () => void;
+  free: () => void;
+}
+
+interface PriorityQueueClass {
+  new <T>(options?: PriorityQueueOptions): PriorityQueue<T>;
+  from_array: <T>(
+    array: T[],
+    options?: PriorityQueueOptions
+  ) => PriorityQueue<T>;
+}
+
+export interface Reservoir<T> {
+  readonly seen: number;
+  push: (item: T) => void;
+  push_all: (items: T[]) => void;
+  sample: () => T[];
+  free: () => void;
+}
+
+interface ReservoirClass {
+  new <T>(k: number, seed?: number): Reservoir<T>;
+}
+
+export type ColumnType = "f64" | "i64" | "string" | "bool";
+
+export type TableSchema = Record<string, ColumnType>;
+
+// `i64` cells come back as BigInts and missing cells as null
+export type TableCell = number | bigint | string | boolean | null;
+
+export type TableColumn =
+  | Float64Array
+  | BigInt64Array
+  | BigUint64Array
+  | TableCell[];
+
+export interface TableCondition {
+  column: string;
+  op?:
+    | "=="
+    | "!="
+    | "<"
+    | "<="
+    | ">"
+    | ">="
+    | "contains"
+    | "startsWith"
+    | "endsWith";
+  value: number | bigint | string | boolean;
+}
+
+export interface Table {
+  readonly length: number;
+  column_names: () => string[];
+  schema: () => TableSchema;
+  column: (name: string) => TableColumn;
+  row: (index: number) => Record<string, TableCell> | undefined;
+  to_rows: () => Record<string, TableCell>[];
+  sort: (spec: SortSpec, options?: SortOptions) => Table;
+  filter: (conditions: TableCondition | TableCondition[]) => Table;
+  select: (names: string[]) => Table;
+  slice: (start: number, count: number) => Table;
+  free: () => void;
+}
+
+interface TableClass {
+  from_rows: (rows: object[], schema?: TableSchema) => Table;
+  from_columns: (
+    columns: Record<string, ArrayLike<unknown>>,
+    schema?: TableSchema
+  ) => Table;
+}
+
 interface WasmModule {
   init_panic_hook: () => void;
-  sort_array: <T>(array: T[], predicate: SortPredicate<T>) => T[];
-  sort_numbers: (numbers: number[], ascending: boolean) => number[];
-  sort_strings: (strings: string[], ascending: boolean) => string[];
+  sort_array: <T>(
+    array: T[],
+    predicate?: SortPredicate<T>,
+    options?: SortArrayOptions
+  ) => T[];
+  sort_by_keys: <T>(array: T[], spec: SortSpec, options?: SortOptions) => T[];
+  sort_json: (json: string, spec: SortSpec, options?: SortOptions) => string;
+  sort_photos: (
+    photos: Photo[],
+    field: PhotoField,
+    ascending: boolean,
+    options?: StringSortOptions
+  ) => Photo[];
+  validate_comparator: <T>(
+    sample: T[],
+    predicate: SortPredicate<T>,
+    options?: ValidateComparatorOptions
+  ) => ComparatorReport;
+  sort_by_expression: <T>(
+    array: T[],
+    expression: string,
+    options?: CollationOptions & SortOptions
+  ) => T[];
+  filter_by_expression: <T>(
+    array: T[],
+    expression: string,
+    options?: CollationOptions
+  ) => T[];
+  filter_indices_by_expression: <T>(
+    array: T[],
+    expression: string,
+    options?: CollationOptions
+  ) => Uint32Array;
+  is_stable_result: <T>(original: T[], sorted: T[], spec: SortSpec) => boolean;
+  sort_numbers: (
+    numbers: unknown[],
+    ascending: boolean,
+    options?: NumberSortOptions
+  ) => SortReport<number>;
+  sort_strings: (
+    strings: unknown[],
+    ascending: boolean,
+    options?: StringSortOptions
+  ) => SortReport<string>;
+  sort_values: <T>(
+    array: T[],
+    ascending: boolean,
+    options?: Omit<SortKeySpec, "path" | "direction"> & SortOptions
+  ) => T[];
+  sort_f64: (
+    values: Float64Array,
+    ascending: boolean,
+    options?: FloatSortOptions
+  ) => void;
+  sort_f32: (
+    values: Float32Array,
+    ascending: boolean,
+    options?: FloatSortOptions
+  ) => void;
+  sort_i32: (
+    values: Int32Array,
+    ascending: boolean,
+    options?: SortOptions
+  ) => void;
+  sort_u32: (
+    values: Uint32Array,
+    ascending: boolean,
+    options?: SortOptions
+  ) => void;
+  argsort_numbers: (
+    numbers: number[],
+    ascending: boolean,
+    options?: FloatOrderOptions & SortOptions
+  ) => Uint32Array;
+  argsort_strings: (
+    strings: string[],
+    ascending: boolean,
+    options?: CollationOptions & SortOptions
+  ) => Uint32Array;
+  argsort_by_keys: <T>(
+    array: T[],
+    spec: SortSpec,
+    options?: SortOptions
+  ) => Uint32Array;
+  top_k: <T>(array: T[], k: number, spec: SortSpec) => T[];
+  partial_sort: <T>(array: T[], k: number, spec: SortSpec) => T[];
+  select_nth: <T>(array: T[], n: number, spec: SortSpec) => T | undefined;
+  top_k_f64: (
+    values: Float64Array,
+    k: number,
+    ascending: boolean,
+    options?: FloatOrderOptions
+  ) => Float64Array;
+  sort_page: <T>(
+    array: T[],
+    spec: SortSpec,
+    offset: number,
+    limit: number,
+    options?: SortPageOptions
+  ) => SortPage<T>;
+  clear_sort_cache: () => void;
+  SortedCollection: SortedCollectionClass;
+  PriorityQueue: PriorityQueueClass;
+  shuffle: <T>(array: T[], seed?: number) => T[];
+  shuffle_f64: (values: Float64Array, seed?: number) => void;
+  sample: <T>(array: T[], k: number, seed?: number) => T[];
+  Reservoir: ReservoirClass;
+  Table: TableClass;
   // Mathematical computation functions
   test_simple_math: (a: number, b: number) => number;
   monte_carlo_pi: (iterations: number) => number;
@@ -46,8 +400,37 @@ export const useWasm = () => {
         setWasm({
           init_panic_hook: wasmModule.init_panic_hook,
           sort_array: wasmModule.sort_array,
+          sort_by_keys: wasmModule.sort_by_keys,
+          sort_json: wasmModule.sort_json,
+          sort_photos: wasmModule.sort_photos,
+          validate_comparator: wasmModule.validate_comparator,
+          sort_by_expression: wasmModule.sort_by_expression,
+          filter_by_expression: wasmModule.filter_by_expression,
+          filter_indices_by_expression: wasmModule.filter_indices_by_expression,
+          is_stable_result: wasmModule.is_stable_result,
           sort_numbers: wasmModule.sort_numbers,
           sort_strings: wasmModule.sort_strings,
+          sort_values: wasmModule.sort_values,
+          sort_f64: wasmModule.sort_f64,
+          sort_f32: wasmModule.sort_f32,
+          sort_i32: wasmModule.sort_i32,
+          sort_u32: wasmModule.sort_u32,
+          argsort_numbers: wasmModule.argsort_numbers,
+          argsort_strings: wasmModule.argsort_strings,
+          argsort_by_keys: wasmModule.argsort_by_keys,
+          top_k: wasmModule.top_k,
+          partial_sort: wasmModule.partial_sort,
+          select_nth: wasmModule.select_nth,
+          top_k_f64: wasmModule.top_k_f64,
+          sort_page: wasmModule.sort_page,
+          clear_sort_cache: wasmModule.clear_sort_cache,
+          SortedCollection: wasmModule.SortedCollection,
+          PriorityQueue: wasmModule.PriorityQueue,
+          shuffle: wasmModule.shuffle,
+          shuffle_f64: wasmModule.shuffle_f64,
+          sample: wasmModule.sample,
+          Reservoir: wasmModule.Reservoir,
+          Table: wasmModule.Table,
           test_simple_math: wasmModule.test_simple_math,
           monte_carlo_pi: wasmModule.monte_carlo_pi,
           mandelbrot_set: wasmModule.mandelbrot_set,
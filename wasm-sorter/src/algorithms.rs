@@ -0,0 +1,907 @@
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+use js_sys::{Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+use crate::js_error;
+use crate::options::{get_bool, get_string};
+
+/// Slices up to this length are finished with insertion sort by the
+/// quicksort variants.
+const INSERTION_THRESHOLD: usize = 20;
+
+/// The sorting algorithm a sort entry point runs.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortAlgorithm {
+    /// The standard library sort, or radix sort for large numeric inputs.
+    Auto,
+    /// Always the standard library sort.
+    Comparison,
+    Insertion,
+    Merge,
+    Heap,
+    Quick,
+    Pdq,
+    Timsort,
+    /// LSD radix sort. Only numeric sorts support it.
+    Radix,
+}
+
+impl SortAlgorithm {
+    /// Reads `algorithm` from an options object.
+    pub fn from_options(options: &JsValue) -> Result<SortAlgorithm, JsValue> {
+        match get_string(options, "algorithm")?.as_deref() {
+            None | Some("auto") => Ok(SortAlgorithm::Auto),
+            Some("comparison") => Ok(SortAlgorithm::Comparison),
+            Some("insertion") => Ok(SortAlgorithm::Insertion),
+            Some("merge") => Ok(SortAlgorithm::Merge),
+            Some("heap") => Ok(SortAlgorithm::Heap),
+            Some("quick") => Ok(SortAlgorithm::Quick),
+            Some("pdq") => Ok(SortAlgorithm::Pdq),
+            Some("timsort") => Ok(SortAlgorithm::Timsort),
+            Some("radix") => Ok(SortAlgorithm::Radix),
+            Some(other) => Err(js_error(&format!(
+                "unknown algorithm '{}', expected one of 'auto', 'comparison', \
+                 'insertion', 'merge', 'heap', 'quick', 'pdq', 'timsort' or 'radix'",
+                other
+            ))),
+        }
+    }
+
+    /// Like `from_options`, but rejects radix sort for sorts that have no
+    /// numeric keys to run it on.
+    pub fn comparison_from_options(options: &JsValue) -> Result<SortAlgorithm, JsValue> {
+        let algorithm = SortAlgorithm::from_options(options)?;
+        if algorithm == SortAlgorithm::Radix {
+            return Err(js_error("radix sort is only available for numeric sorts"));
+        }
+        Ok(algorithm)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SortAlgorithm::Auto | SortAlgorithm::Comparison => "comparison",
+            SortAlgorithm::Insertion => "insertion",
+            SortAlgorithm::Merge => "merge",
+            SortAlgorithm::Heap => "heap",
+            SortAlgorithm::Quick => "quick",
+            SortAlgorithm::Pdq => "pdq",
+            SortAlgorithm::Timsort => "timsort",
+            SortAlgorithm::Radix => "radix",
+        }
+    }
+
+    /// Whether the algorithm keeps equal elements in their input order, or
+    /// `None` for the standard library sorts, which come in both kinds.
+    pub fn stability(self) -> Option<bool> {
+        match self {
+            SortAlgorithm::Auto | SortAlgorithm::Comparison => None,
+            SortAlgorithm::Insertion
+            | SortAlgorithm::Merge
+            | SortAlgorithm::Timsort
+            | SortAlgorithm::Radix => Some(true),
+            SortAlgorithm::Heap | SortAlgorithm::Quick | SortAlgorithm::Pdq => Some(false),
+        }
+    }
+}
+
+/// Reads `stable` from an options object. `stable: false` picks the
+/// standard library's unstable sort; without the option it is stable when
+/// `default` is set. The crate's own algorithms always run as they are, so
+/// asking for a stable heap sort, quicksort or pdqsort is an error.
+pub fn stable_from_options(
+    options: &JsValue,
+    algorithm: SortAlgorithm,
+    default: bool,
+) -> Result<bool, JsValue> {
+    match (get_bool(options, "stable")?, algorithm.stability()) {
+        (Some(true), Some(false)) => Err(js_error(&format!(
+            "{} sort is not stable, use 'merge', 'insertion', 'timsort' or 'auto'",
+            algorithm.name()
+        ))),
+        (_, Some(stable)) => Ok(stable),
+        (requested, None) => Ok(requested.unwrap_or(default)),
+    }
+}
+
+/// Counters collected while sorting.
+pub struct SortStats {
+    /// The algorithm that actually ran, after resolving `Auto`.
+    pub algorithm: SortAlgorithm,
+    pub comparisons: u64,
+    /// Element writes. `None` for the standard library sort, which does not
+    /// expose them.
+    pub moves: Option<u64>,
+    /// Calls into a JS comparator.
+    pub callbacks: u64,
+    /// Whether equal elements kept their input order.
+    pub stable: bool,
+    /// Whether the result was a cached sort, so none ran and nothing was
+    /// counted.
+    pub cached: bool,
+}
+
+impl SortStats {
+    pub fn new() -> SortStats {
+        SortStats {
+            algorithm: SortAlgorithm::Comparison,
+            comparisons: 0,
+            moves: None,
+            callbacks: 0,
+            stable: true,
+            cached: false,
+        }
+    }
+
+    /// Writes the counters into `options.stats` when the caller passed an
+    /// object there, e.g. `sort_array(data, cmp, { algorithm: "heap", stats: {} })`.
+    pub fn report(&self, options: &JsValue) -> Result<(), JsValue> {
+        if options.is_undefined() || options.is_null() {
+            return Ok(());
+        }
+        let target = Reflect::get(options, &JsValue::from_str("stats"))?;
+        if !target.is_object() {
+            return Ok(());
+        }
+
+        let target = Object::from(target);
+        let set = |name: &str, value: JsValue| {
+            Reflect::set(&target, &JsValue::from_str(name), &value).map(|_| ())
+        };
+        set("algorithm", JsValue::from_str(self.algorithm.name()))?;
+        set("comparisons", JsValue::from_f64(self.comparisons as f64))?;
+        set(
+            "moves",
+            self.moves
+                .map_or(JsValue::NULL, |moves| JsValue::from_f64(moves as f64)),
+        )?;
+        set("callbacks", JsValue::from_f64(self.callbacks as f64))?;
+        set("stable", JsValue::from_bool(self.stable))?;
+        set("cached", JsValue::from_bool(self.cached))?;
+        Ok(())
+    }
+}
+
+/// Sorts `v` with a comparison-based algorithm and adds the comparisons and
+/// moves it made to `stats`. `Auto` and `Comparison` run the standard
+/// library's stable sort, or its unstable sort when `stable` is false.
+pub fn sort_by<T, F>(
+    v: &mut [T],
+    algorithm: SortAlgorithm,
+    stable: bool,
+    stats: &mut SortStats,
+    compare: F,
+) where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut sorter = Sorter {
+        compare,
+        comparisons: 0,
+        moves: 0,
+        _element: PhantomData,
+    };
+
+    match algorithm {
+        SortAlgorithm::Auto | SortAlgorithm::Comparison | SortAlgorithm::Radix => {
+            let comparisons = &mut sorter.comparisons;
+            let compare = &mut sorter.compare;
+            let counted = |a: &T, b: &T| {
+                *comparisons += 1;
+                compare(a, b)
+            };
+            if stable {
+                v.sort_by(counted);
+            } else {
+                v.sort_unstable_by(counted);
+            }
+            stats.algorithm = SortAlgorithm::Comparison;
+            stats.comparisons += sorter.comparisons;
+            stats.moves = None;
+            stats.stable = stable;
+            return;
+        }
+        SortAlgorithm::Insertion => sorter.insertion_sort(v),
+        SortAlgorithm::Merge => sorter.merge_sort(v),
+        SortAlgorithm::Heap => sorter.heap_sort(v),
+        SortAlgorithm::Quick => sorter.quick_sort(v),
+        SortAlgorithm::Pdq => sorter.pdq_sort(v),
+        SortAlgorithm::Timsort => sorter.tim_sort(v),
+    }
+
+    stats.algorithm = algorithm;
+    stats.stable = algorithm.stability().unwrap_or(stable);
+    stats.comparisons += sorter.comparisons;
+    stats.moves = Some(stats.moves.unwrap_or(0) + sorter.moves);
+}
+
+/// Like `sort_by`, for comparators that may not define a total order, such
+/// as JS functions. The standard library sorts are allowed to panic on those,
+/// which traps the WASM module, so `Auto` and `Comparison` run timsort
+/// instead. The crate's own algorithms stay in bounds and return a
+/// permutation of `v` whatever the comparator returns.
+pub fn sort_by_untrusted<T, F>(
+    v: &mut [T],
+    algorithm: SortAlgorithm,
+    stable: bool,
+    stats: &mut SortStats,
+    compare: F,
+) where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let algorithm = match algorithm {
+        SortAlgorithm::Auto | SortAlgorithm::Comparison | SortAlgorithm::Radix => {
+            SortAlgorithm::Timsort
+        }
+        other => other,
+    };
+    sort_by(v, algorithm, stable, stats, compare);
+}
+
+/// Runs the crate's own sorting algorithms while counting comparisons and
+/// element writes.
+struct Sorter<T, F> {
+    compare: F,
+    comparisons: u64,
+    moves: u64,
+    _element: PhantomData<T>,
+}
+
+impl<T, F> Sorter<T, F>
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    fn less(&mut self, a: &T, b: &T) -> bool {
+        self.comparisons += 1;
+        (self.compare)(a, b) == Ordering::Less
+    }
+
+    fn write(&mut self, v: &mut [T], index: usize, value: T) {
+        v[index] = value;
+        self.moves += 1;
+    }
+
+    fn swap(&mut self, v: &mut [T], a: usize, b: usize) {
+        if a != b {
+            v.swap(a, b);
+            self.moves += 2;
+        }
+    }
+
+    // Insertion sort
+
+    /// Inserts the last element of `v` into the sorted prefix before it.
+    fn shift_tail(&mut self, v: &mut [T]) {
+        let len = v.len();
+        if len < 2 || !self.less(&v[len - 1], &v[len - 2]) {
+            return;
+        }
+        let value = v[len - 1];
+        let mut hole = len - 1;
+        while hole > 0 && self.less(&value, &v[hole - 1]) {
+            let prev = v[hole - 1];
+            self.write(v, hole, prev);
+            hole -= 1;
+        }
+        self.write(v, hole, value);
+    }
+
+    /// Inserts the first element of `v` into the sorted suffix after it.
+    fn shift_head(&mut self, v: &mut [T]) {
+        let len = v.len();
+        if len < 2 || !self.less(&v[1], &v[0]) {
+            return;
+        }
+        let value = v[0];
+        let mut hole = 0;
+        while hole + 1 < len && self.less(&v[hole + 1], &value) {
+            let next = v[hole + 1];
+            self.write(v, hole, next);
+            hole += 1;
+        }
+        self.write(v, hole, value);
+    }
+
+    fn insertion_sort(&mut self, v: &mut [T]) {
+        for end in 2..=v.len() {
+            self.shift_tail(&mut v[..end]);
+        }
+    }
+
+    /// Extends the sorted prefix `v[..sorted]` to the whole slice, finding
+    /// each insertion point by binary search.
+    fn binary_insertion_sort(&mut self, v: &mut [T], sorted: usize) {
+        for i in sorted.max(1)..v.len() {
+            let value = v[i];
+            let (mut lo, mut hi) = (0, i);
+            while lo < hi {
+                let mid = (lo + hi) / 2;
+                if self.less(&value, &v[mid]) {
+                    hi = mid;
+                } else {
+                    lo = mid + 1;
+                }
+            }
+            if lo < i {
+                v.copy_within(lo..i, lo + 1);
+                self.moves += (i - lo) as u64;
+                self.write(v, lo, value);
+            }
+        }
+    }
+
+    // Merge sort
+
+    fn merge_sort(&mut self, v: &mut [T]) {
+        let mut buffer = v.to_vec();
+        self.merge_sort_into(v, &mut buffer);
+    }
+
+    fn merge_sort_into(&mut self, v: &mut [T], buffer: &mut [T]) {
+        let len = v.len();
+        if len < 2 {
+            return;
+        }
+        let mid = len / 2;
+        self.merge_sort_into(&mut v[..mid], &mut buffer[..mid]);
+        self.merge_sort_into(&mut v[mid..], &mut buffer[mid..]);
+        self.merge(v, mid, buffer);
+    }
+
+    /// Merges the sorted runs `v[..mid]` and `v[mid..]`, using `buffer` to
+    /// hold the left run. Equal elements keep their order.
+    fn merge(&mut self, v: &mut [T], mid: usize, buffer: &mut [T]) {
+        let len = v.len();
+        if mid == 0 || mid == len || !self.less(&v[mid], &v[mid - 1]) {
+            return;
+        }
+
+        buffer[..mid].copy_from_slice(&v[..mid]);
+        self.moves += mid as u64;
+
+        let (mut left, mut right, mut out) = (0, mid, 0);
+        while left < mid && right < len {
+            let value = if self.less(&v[right], &buffer[left]) {
+                right += 1;
+                v[right - 1]
+            } else {
+                left += 1;
+                buffer[left - 1]
+            };
+            self.write(v, out, value);
+            out += 1;
+        }
+        // Whatever is left of the right run is already in place
+        while left < mid {
+            let value = buffer[left];
+            self.write(v, out, value);
+            left += 1;
+            out += 1;
+        }
+    }
+
+    // Heap sort
+
+    fn sift_down(&mut self, v: &mut [T], mut node: usize, end: usize) {
+        loop {
+            let mut child = 2 * node + 1;
+            if child >= end {
+                return;
+            }
+            if child + 1 < end && self.less(&v[child], &v[child + 1]) {
+                child += 1;
+            }
+            if !self.less(&v[node], &v[child]) {
+                return;
+            }
+            self.swap(v, node, child);
+            node = child;
+        }
+    }
+
+    fn heap_sort(&mut self, v: &mut [T]) {
+        let len = v.len();
+        for node in (0..len / 2).rev() {
+            self.sift_down(v, node, len);
+        }
+        for end in (1..len).rev() {
+            self.swap(v, 0, end);
+            self.sift_down(v, 0, end);
+        }
+    }
+
+    // Quicksort
+
+    /// Returns the index of the median of `v[a]`, `v[b]` and `v[c]`, and
+    /// whether the three were already in order.
+    fn median_of_three(&mut self, v: &[T], a: usize, b: usize, c: usize) -> (usize, bool) {
+        if self.less(&v[b], &v[a]) {
+            if self.less(&v[c], &v[b]) {
+                (b, false)
+            } else if self.less(&v[c], &v[a]) {
+                (c, false)
+            } else {
+                (a, false)
+            }
+        } else if self.less(&v[c], &v[a]) {
+            (a, false)
+        } else if self.less(&v[c], &v[b]) {
+            (c, false)
+        } else {
+            (b, true)
+        }
+    }
+
+    /// Hoare partition around `v[pivot]`. Returns the pivot's final index and
+    /// whether the slice was already partitioned (no swaps were needed).
+    fn partition(&mut self, v: &mut [T], pivot: usize) -> (usize, bool) {
+        self.swap(v, 0, pivot);
+        let pivot = v[0];
+        let (mut i, mut j) = (1, v.len() - 1);
+        let mut partitioned = true;
+
+        loop {
+            while i <= j && self.less(&v[i], &pivot) {
+                i += 1;
+            }
+            while i <= j && self.less(&pivot, &v[j]) {
+                j -= 1;
+            }
+            if i >= j {
+                break;
+            }
+            self.swap(v, i, j);
+            partitioned = false;
+            i += 1;
+            j -= 1;
+        }
+
+        self.swap(v, 0, j);
+        (j, partitioned)
+    }
+
+    fn quick_sort(&mut self, mut v: &mut [T]) {
+        loop {
+            let len = v.len();
+            if len <= INSERTION_THRESHOLD {
+                self.insertion_sort(v);
+                return;
+            }
+
+            let (pivot, _) = self.median_of_three(v, 0, len / 2, len - 1);
+            let (mid, _) = self.partition(v, pivot);
+
+            // Recurse into the smaller side to bound the stack depth
+            let (left, right) = std::mem::take(&mut v).split_at_mut(mid);
+            let right = &mut right[1..];
+            if left.len() < right.len() {
+                self.quick_sort(left);
+                v = right;
+            } else {
+                self.quick_sort(right);
+                v = left;
+            }
+        }
+    }
+
+    // Pattern-defeating quicksort
+
+    fn pdq_sort(&mut self, v: &mut [T]) {
+        let limit = usize::BITS - v.len().leading_zeros();
+        self.pdq_recurse(v, None, limit);
+    }
+
+    /// Picks a pivot: the median of three for short slices, the median of
+    /// three medians otherwise. Also reports whether the samples were
+    /// already in order, which hints that the slice may be sorted.
+    fn choose_pivot(&mut self, v: &[T]) -> (usize, bool) {
+        let len = v.len();
+        let (a, b, c) = (len / 4, len / 2, len / 4 * 3);
+
+        if len >= 128 {
+            let (a, sorted_a) = self.median_of_three(v, a - 1, a, a + 1);
+            let (b, sorted_b) = self.median_of_three(v, b - 1, b, b + 1);
+            let (c, sorted_c) = self.median_of_three(v, c - 1, c, c + 1);
+            let (pivot, sorted) = self.median_of_three(v, a, b, c);
+            (pivot, sorted && sorted_a && sorted_b && sorted_c)
+        } else {
+            self.median_of_three(v, a, b, c)
+        }
+    }
+
+    /// Puts every element equal to `v[pivot]` at the front, assuming no
+    /// element is smaller. Returns the number of such elements.
+    fn partition_equal(&mut self, v: &mut [T], pivot: usize) -> usize {
+        self.swap(v, 0, pivot);
+        let pivot = v[0];
+        let (mut l, mut r) = (1, v.len());
+
+        loop {
+            while l < r && !self.less(&pivot, &v[l]) {
+                l += 1;
+            }
+            while l < r && self.less(&pivot, &v[r - 1]) {
+                r -= 1;
+            }
+            if l >= r {
+                return l;
+            }
+            r -= 1;
+            self.swap(v, l, r);
+            l += 1;
+        }
+    }
+
+    /// Tries to sort a nearly sorted slice by fixing a few out-of-order
+    /// pairs. Returns whether the slice ended up sorted.
+    fn partial_insertion_sort(&mut self, v: &mut [T]) -> bool {
+        const MAX_STEPS: usize = 5;
+        const SHORTEST_SHIFTING: usize = 50;
+
+        let len = v.len();
+        let mut i = 1;
+        for _ in 0..MAX_STEPS {
+            while i < len && !self.less(&v[i], &v[i - 1]) {
+                i += 1;
+            }
+            if i == len {
+                return true;
+            }
+            if len < SHORTEST_SHIFTING {
+                return false;
+            }
+
+            self.swap(v, i - 1, i);
+            self.shift_tail(&mut v[..i]);
+            self.shift_head(&mut v[i..]);
+        }
+        false
+    }
+
+    /// Swaps a few elements around the middle to break up patterns that
+    /// keep producing unbalanced partitions.
+    fn break_patterns(&mut self, v: &mut [T]) {
+        let len = v.len();
+        let mut random = len as u32;
+        let mask = len.next_power_of_two() - 1;
+        let pos = len / 4 * 2;
+
+        for i in 0..3 {
+            random ^= random << 13;
+            random ^= random >> 17;
+            random ^= random << 5;
+            let mut other = random as usize & mask;
+            if other >= len {
+                other -= len;
+            }
+            self.swap(v, pos - 1 + i, other);
+        }
+    }
+
+    fn pdq_recurse(&mut self, mut v: &mut [T], mut pred: Option<T>, mut limit: u32) {
+        let mut was_balanced = true;
+        let mut was_partitioned = true;
+
+        loop {
+            let len = v.len();
+            if len <= INSERTION_THRESHOLD {
+                self.insertion_sort(v);
+                return;
+            }
+            // Too many bad pivots: fall back to guaranteed O(n log n)
+            if limit == 0 {
+                self.heap_sort(v);
+                return;
+            }
+            if !was_balanced {
+                self.break_patterns(v);
+                limit -= 1;
+            }
+
+            let (pivot, likely_sorted) = self.choose_pivot(v);
+            if was_balanced && was_partitioned && likely_sorted && self.partial_insertion_sort(v) {
+                return;
+            }
+
+            // A pivot equal to the predecessor means this slice starts with a
+            // run of duplicates; skip past them in one pass
+            if let Some(pred) = pred {
+                if !self.less(&pred, &v[pivot]) {
+                    let mid = self.partition_equal(v, pivot);
+                    v = &mut std::mem::take(&mut v)[mid..];
+                    continue;
+                }
+            }
+
+            let (mid, partitioned) = self.partition(v, pivot);
+            was_balanced = mid.min(len - mid) >= len / 8;
+            was_partitioned = partitioned;
+
+            let (left, right) = std::mem::take(&mut v).split_at_mut(mid);
+            let (pivot, right) = right.split_at_mut(1);
+            let pivot = pivot[0];
+            if left.len() < right.len() {
+                self.pdq_recurse(left, pred, limit);
+                v = right;
+                pred = Some(pivot);
+            } else {
+                self.pdq_recurse(right, Some(pivot), limit);
+                v = left;
+            }
+        }
+    }
+
+    // Timsort
+
+    /// Returns the length of the run at the start of `v`, reversing it first
+    /// if it is strictly descending.
+    fn count_run(&mut self, v: &mut [T]) -> usize {
+        let len = v.len();
+        if len < 2 {
+            return len;
+        }
+
+        let mut end = 2;
+        if self.less(&v[1], &v[0]) {
+            while end < len && self.less(&v[end], &v[end - 1]) {
+                end += 1;
+            }
+            v[..end].reverse();
+            self.moves += (end / 2 * 2) as u64;
+        } else {
+            while end < len && !self.less(&v[end], &v[end - 1]) {
+                end += 1;
+            }
+        }
+        end
+    }
+
+    fn tim_sort(&mut self, v: &mut [T]) {
+        let len = v.len();
+        if len < 2 {
+            return;
+        }
+
+        let min_run = {
+            let (mut n, mut r) = (len, 0);
+            while n >= 64 {
+                r |= n & 1;
+                n >>= 1;
+            }
+            n + r
+        };
+
+        let mut buffer = v.to_vec();
+        // (start, length) of the runs waiting to be merged
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        let mut start = 0;
+
+        while start < len {
+            let mut run = self.count_run(&mut v[start..]);
+            if run < min_run {
+                let end = (start + min_run).min(len);
+                self.binary_insertion_sort(&mut v[start..end], run);
+                run = end - start;
+            }
+            runs.push((start, run));
+            start += run;
+
+            // Keep run lengths shrinking faster than Fibonacci numbers so
+            // the stack stays logarithmic and merges stay balanced
+            while runs.len() > 1 {
+                let mut n = runs.len() - 2;
+                if (n >= 1 && runs[n - 1].1 <= runs[n].1 + runs[n + 1].1)
+                    || (n >= 2 && runs[n - 2].1 <= runs[n - 1].1 + runs[n].1)
+                {
+                    if runs[n - 1].1 < runs[n + 1].1 {
+                        n -= 1;
+                    }
+                } else if runs[n].1 > runs[n + 1].1 {
+                    break;
+                }
+                self.merge_runs(v, &mut runs, n, &mut buffer);
+            }
+        }
+
+        while runs.len() > 1 {
+            let n = runs.len() - 2;
+            self.merge_runs(v, &mut runs, n, &mut buffer);
+        }
+    }
+
+    fn merge_runs(
+        &mut self,
+        v: &mut [T],
+        runs: &mut Vec<(usize, usize)>,
+        n: usize,
+        buffer: &mut [T],
+    ) {
+        let (start, left) = runs[n];
+        let right = runs[n + 1].1;
+        self.merge(&mut v[start..start + left + right], left, buffer);
+        runs[n].1 = left + right;
+        runs.remove(n + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numeric::FloatOrder;
+    use crate::rng::Rng;
+
+    const ALGORITHMS: [SortAlgorithm; 8] = [
+        SortAlgorithm::Auto,
+        SortAlgorithm::Comparison,
+        SortAlgorithm::Insertion,
+        SortAlgorithm::Merge,
+        SortAlgorithm::Heap,
+        SortAlgorithm::Quick,
+        SortAlgorithm::Pdq,
+        SortAlgorithm::Timsort,
+    ];
+
+    fn is_permutation(sorted: &[usize], len: usize) -> bool {
+        let mut seen = sorted.to_vec();
+        seen.sort_unstable();
+        seen == (0..len).collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn untrusted_sort_survives_a_comparator_that_fails_partway() {
+        let mut rng = Rng::new(7);
+        for len in [0, 1, 2, 20, 21, 100, 1000] {
+            let keys: Vec<u64> = (0..len).map(|_| rng.below(50)).collect();
+            for fail_after in [0, 1, 10, 100, 1000] {
+                for algorithm in ALGORITHMS {
+                    let mut order: Vec<usize> = (0..len).collect();
+                    let mut calls = 0;
+                    let mut stats = SortStats::new();
+                    // Like `sort_array` after the predicate threw
+                    sort_by_untrusted(&mut order, algorithm, true, &mut stats, |&a, &b| {
+                        calls += 1;
+                        if calls > fail_after {
+                            Ordering::Equal
+                        } else {
+                            keys[a].cmp(&keys[b])
+                        }
+                    });
+                    assert!(is_permutation(&order, len));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn untrusted_sort_survives_a_random_comparator() {
+        let mut rng = Rng::new(11);
+        for len in [2, 19, 64, 129, 1000] {
+            for algorithm in ALGORITHMS {
+                let mut order: Vec<usize> = (0..len).collect();
+                let mut stats = SortStats::new();
+                sort_by_untrusted(&mut order, algorithm, false, &mut stats, |_, _| {
+                    match rng.below(3) {
+                        0 => Ordering::Less,
+                        1 => Ordering::Equal,
+                        _ => Ordering::Greater,
+                    }
+                });
+                assert!(is_permutation(&order, len));
+            }
+        }
+    }
+
+    #[test]
+    fn untrusted_sort_runs_timsort_for_auto() {
+        let mut values = vec![3, 1, 2];
+        let mut stats = SortStats::new();
+        sort_by_untrusted(
+            &mut values,
+            SortAlgorithm::Auto,
+            false,
+            &mut stats,
+            |a, b| a.cmp(b),
+        );
+        assert_eq!(values, [1, 2, 3]);
+        assert!(stats.algorithm == SortAlgorithm::Timsort);
+        assert!(stats.stable);
+    }
+
+    /// Inputs of `len` keys that exercise runs, duplicates and the
+    /// insertion sort cutoff.
+    fn patterns(rng: &mut Rng, len: usize) -> Vec<Vec<u64>> {
+        let len64 = len as u64;
+        vec![
+            (0..len).map(|_| rng.below(len64.max(1) * 4)).collect(),
+            (0..len).map(|_| rng.below(4)).collect(),
+            vec![7; len],
+            (0..len64).collect(),
+            (0..len64).rev().collect(),
+            (0..len64).map(|i| i.min(len64 - i)).collect(),
+            (0..len64).map(|i| (i % 50) * 3 + (i / 50) % 2).collect(),
+        ]
+    }
+
+    #[test]
+    fn every_algorithm_sorts_like_the_standard_sort() {
+        let mut rng = Rng::new(3);
+        for len in [0, 1, 2, 3, 19, 20, 21, 64, 65, 257, 1000] {
+            for keys in patterns(&mut rng, len) {
+                let mut expected: Vec<usize> = (0..len).collect();
+                expected.sort_by(|&a, &b| keys[a].cmp(&keys[b]));
+
+                for algorithm in ALGORITHMS {
+                    for stable in [true, false] {
+                        if stable && algorithm.stability() == Some(false) {
+                            continue;
+                        }
+                        let mut order: Vec<usize> = (0..len).collect();
+                        let mut stats = SortStats::new();
+                        sort_by(&mut order, algorithm, stable, &mut stats, |&a, &b| {
+                            keys[a].cmp(&keys[b])
+                        });
+
+                        assert!(is_permutation(&order, len));
+                        assert!(order.windows(2).all(|w| keys[w[0]] <= keys[w[1]]));
+                        if stats.stable {
+                            assert_eq!(order, expected, "{} on {:?}", algorithm.name(), keys);
+                        }
+                        assert_eq!(stats.stable, algorithm.stability().unwrap_or(stable));
+                        if len > 1 {
+                            assert!(stats.comparisons > 0);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_algorithm_handles_signed_zeros_and_nan() {
+        let values = [
+            0.0,
+            f64::NAN,
+            -0.0,
+            1.5,
+            f64::NEG_INFINITY,
+            -0.0,
+            f64::NAN,
+            0.0,
+            -1.5,
+            f64::INFINITY,
+        ];
+        for nan_first in [false, true] {
+            for signed_zero in [false, true] {
+                let order = FloatOrder {
+                    nan_first,
+                    signed_zero,
+                };
+                let compare = |a: &f64, b: &f64| order.compare(*a, *b, false);
+                let mut expected = values.to_vec();
+                expected.sort_by(compare);
+
+                for algorithm in ALGORITHMS {
+                    let mut sorted = values.to_vec();
+                    let mut stats = SortStats::new();
+                    sort_by(&mut sorted, algorithm, true, &mut stats, compare);
+                    assert!(sorted.windows(2).all(|w| compare(&w[0], &w[1]).is_le()));
+                    // Same values, NaN and the sign of zero included
+                    let bits = |v: &[f64]| {
+                        let mut bits: Vec<u64> = v.iter().map(|n| n.to_bits()).collect();
+                        bits.sort_unstable();
+                        bits
+                    };
+                    assert_eq!(bits(&sorted), bits(&values));
+                    if stats.stable {
+                        let bits: Vec<u64> = sorted.iter().map(|n| n.to_bits()).collect();
+                        let expected: Vec<u64> = expected.iter().map(|n| n.to_bits()).collect();
+                        assert_eq!(bits, expected, "{}", algorithm.name());
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::cmp::Ordering;
+
+use icu_collator::{Collator, CollatorOptions, Numeric, Strength};
+use wasm_bindgen::prelude::*;
+
+use crate::js_error;
+use crate::options::{get_bool, get_string};
+
+/// How strings are compared.
+pub enum StringOrder {
+    /// Code point order, the same as Rust's `str::cmp`.
+    Binary,
+    /// Code point order, except that runs of ASCII digits compare by their
+    /// numeric value, so "Set 2" sorts before "Set 10".
+    Natural,
+    /// The Unicode Collation Algorithm with the root locale.
+    Collator(Box<Collator>),
+}
+
+impl StringOrder {
+    /// Reads `collation` (`"binary"` or `"uca"`), `numeric` and, for UCA,
+    /// `strength` (`"base"`, `"accent"` or `"case"`) from an options object.
+    pub fn from_options(options: &JsValue) -> Result<StringOrder, JsValue> {
+        let collation = get_string(options, "collation")?;
+        let strength = get_string(options, "strength")?;
+        let numeric = get_bool(options, "numeric")?.unwrap_or(false);
+
+        match collation.as_deref() {
+            None | Some("binary") => {
+                if strength.is_some() {
+                    return Err(js_error("'strength' requires collation 'uca'"));
+                }
+                Ok(if numeric {
+                    StringOrder::Natural
+                } else {
+                    StringOrder::Binary
+                })
+            }
+            Some("uca") => {
+                let mut collator_options = CollatorOptions::new();
+                collator_options.strength = Some(match strength.as_deref() {
+                    Some("base") => Strength::Primary,
+                    Some("accent") => Strength::Secondary,
+                    // Matches the default sensitivity of `localeCompare`
+                    None | Some("case") => Strength::Tertiary,
+                    Some(other) => {
+                        return Err(js_error(&format!(
+                            "unknown strength '{}', expected 'base', 'accent' or 'case'",
+                            other
+                        )))
+                    }
+                });
+                if numeric {
+                    collator_options.numeric = Some(Numeric::On);
+                }
+
+                let collator = Collator::try_new(&Default::default(), collator_options)
+                    .map_err(|err| js_error(&format!("failed to create collator: {}", err)))?;
+                Ok(StringOrder::Collator(Box::new(collator)))
+            }
+            Some(other) => Err(js_error(&format!(
+                "unknown collation '{}', expected 'binary' or 'uca'",
+                other
+            ))),
+        }
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self {
+            StringOrder::Binary => a.cmp(b),
+            StringOrder::Natural => natural_cmp(a, b),
+            StringOrder::Collator(collator) => collator.compare(a, b),
+        }
+    }
+}
+
+/// Compares strings byte by byte, treating each run of ASCII digits as one
+/// number. Numbers that differ only in leading zeros fall back to plain
+/// string order so the result is still a total order.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (x, y) = (a.as_bytes(), b.as_bytes());
+    let (mut i, mut j) = (0, 0);
+
+    while i < x.len() && j < y.len() {
+        if x[i].is_ascii_digit() && y[j].is_ascii_digit() {
+            let (start_x, start_y) = (i, j);
+            while i < x.len() && x[i].is_ascii_digit() {
+                i += 1;
+            }
+            while j < y.len() && y[j].is_ascii_digit() {
+                j += 1;
+            }
+
+            let digits_x = trim_leading_zeros(&x[start_x..i]);
+            let digits_y = trim_leading_zeros(&y[start_y..j]);
+            // Without leading zeros, a longer run is a larger number
+            let ordering = digits_x
+                .len()
+                .cmp(&digits_y.len())
+                .then_with(|| digits_x.cmp(digits_y));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        } else {
+            let ordering = x[i].cmp(&y[j]);
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+
+    (x.len() - i).cmp(&(y.len() - j)).then_with(|| a.cmp(b))
+}
+
+fn trim_leading_zeros(digits: &[u8]) -> &[u8] {
+    let zeros = digits.iter().take_while(|&&d| d == b'0').count();
+    &digits[zeros..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digit_runs_compare_by_value() {
+        assert_eq!(natural_cmp("Set 2", "Set 10"), Ordering::Less);
+        assert_eq!(natural_cmp("x10y", "x9z"), Ordering::Greater);
+        assert_eq!(natural_cmp("file", "file1"), Ordering::Less);
+        assert_eq!(natural_cmp("a1b2", "a1b10"), Ordering::Less);
+        // Longer than any integer type
+        assert_eq!(
+            natural_cmp("99999999999999999999999", "100000000000000000000000"),
+            Ordering::Less
+        );
+        assert_eq!(natural_cmp("", ""), Ordering::Equal);
+        assert_eq!(natural_cmp("", "0"), Ordering::Less);
+    }
+
+    #[test]
+    fn leading_zeros_only_break_ties() {
+        assert_eq!(natural_cmp("a007", "a7"), Ordering::Less);
+        assert_eq!(natural_cmp("a7", "a007"), Ordering::Greater);
+        assert_eq!(natural_cmp("a007", "a8"), Ordering::Less);
+        assert_eq!(natural_cmp("a0010", "a9"), Ordering::Greater);
+        assert_eq!(natural_cmp("0", "00"), Ordering::Less);
+        assert_eq!(natural_cmp("a01b2", "a1b1"), Ordering::Greater);
+    }
+
+    #[test]
+    fn natural_order_is_total() {
+        let pool = [
+            "", "0", "00", "1", "01", "001", "10", "9", "a", "a0", "a00", "a1", "a01", "a1b",
+            "a10", "a9", "b", "1a", "01a", "x-1", "x 1", "é1", "é01",
+        ];
+        for a in pool {
+            assert_eq!(natural_cmp(a, a), Ordering::Equal);
+            for b in pool {
+                let ordering = natural_cmp(a, b);
+                assert_eq!(ordering, natural_cmp(b, a).reverse());
+                assert_eq!(ordering == Ordering::Equal, a == b);
+                for c in pool {
+                    if ordering.is_le() && natural_cmp(b, c).is_le() {
+                        assert!(natural_cmp(a, c).is_le(), "{:?} {:?} {:?}", a, b, c);
+                    }
+                }
+            }
+        }
+    }
+}
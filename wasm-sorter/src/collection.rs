@@ -0,0 +1,312 @@
+use std::cmp::Ordering;
+use std::ops::Range;
+
+use js_sys::{Array, Object};
+use wasm_bindgen::prelude::*;
+
+use crate::keys::{self, KeyValue, SortKey};
+
+struct Entry {
+    row: Vec<KeyValue>,
+    item: JsValue,
+}
+
+/// A collection that lives in WASM memory and stays ordered by a key
+/// specification as elements are inserted and removed, so live views do not
+/// need to re-sort on every change.
+///
+/// Keys are extracted when an element is inserted. Changing a key property
+/// of an element already in the collection does not move it; remove and
+/// re-insert it instead. Equal elements keep their insertion order.
+#[wasm_bindgen]
+pub struct SortedCollection {
+    keys: Vec<SortKey>,
+    entries: Vec<Entry>,
+}
+
+impl SortedCollection {
+    fn compare(&self, a: &[KeyValue], b: &[KeyValue]) -> Ordering {
+        keys::compare_row_values(&self.keys, a, b)
+    }
+
+    /// The entries whose keys equal `row`.
+    fn equal_range(&self, row: &[KeyValue]) -> Range<usize> {
+        let start = self
+            .entries
+            .partition_point(|entry| self.compare(&entry.row, row) == Ordering::Less);
+        let end = self
+            .entries
+            .partition_point(|entry| self.compare(&entry.row, row) != Ordering::Greater);
+        start..end
+    }
+
+    /// Inserts an entry after all entries with equal keys and returns its
+    /// position.
+    fn insert_entry(&mut self, entry: Entry) -> usize {
+        let index = self.equal_range(&entry.row).end;
+        self.entries.insert(index, entry);
+        index
+    }
+
+    fn extend_entries(&mut self, entries: impl IntoIterator<Item = Entry>) {
+        self.entries.extend(entries);
+        // The existing entries form a sorted run, which the stable sort
+        // merges with the new ones instead of sorting from scratch
+        let keys = &self.keys;
+        self.entries
+            .sort_by(|a, b| keys::compare_row_values(keys, &a.row, &b.row));
+    }
+
+    /// The first entry that is not ordered before a bound from
+    /// `keys::extract_bound`.
+    fn lower_bound(&self, bound: &[KeyValue]) -> usize {
+        self.entries.partition_point(|entry| {
+            keys::compare_to_bound(&self.keys, &entry.row, bound) == Ordering::Less
+        })
+    }
+
+    /// The first entry that is ordered after a bound.
+    fn upper_bound(&self, bound: &[KeyValue]) -> usize {
+        self.entries.partition_point(|entry| {
+            keys::compare_to_bound(&self.keys, &entry.row, bound) != Ordering::Greater
+        })
+    }
+
+    fn items(&self, range: Range<usize>) -> Array {
+        let result_array = Array::new();
+        for entry in &self.entries[range] {
+            result_array.push(&entry.item);
+        }
+        result_array
+    }
+}
+
+#[wasm_bindgen]
+impl SortedCollection {
+    /// Creates an empty collection ordered by `spec`, which takes the same
+    /// forms as the `sort_by_keys` specification.
+    #[wasm_bindgen(constructor)]
+    pub fn new(spec: &JsValue) -> Result<SortedCollection, JsValue> {
+        Ok(SortedCollection {
+            keys: keys::parse_spec(spec)?,
+            entries: Vec::new(),
+        })
+    }
+
+    /// Creates a collection holding the elements of `array`, sorted once.
+    pub fn from_array(array: &Array, spec: &JsValue) -> Result<SortedCollection, JsValue> {
+        let mut collection = SortedCollection::new(spec)?;
+        collection.insert_all(array);
+        Ok(collection)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> u32 {
+        self.entries.len() as u32
+    }
+
+    /// Inserts an element after all elements with equal keys and returns
+    /// the position it was inserted at.
+    pub fn insert(&mut self, item: JsValue) -> u32 {
+        for key in &mut self.keys {
+            key.infer_kind(std::slice::from_ref(&item), SortKey::extract);
+        }
+        let row = keys::extract_row(&self.keys, &item);
+        self.insert_entry(Entry { row, item }) as u32
+    }
+
+    /// Inserts every element of `array`. Cheaper than calling `insert` for
+    /// each when the batch is large.
+    pub fn insert_all(&mut self, array: &Array) {
+        let items: Vec<JsValue> = array.iter().collect();
+        for key in &mut self.keys {
+            key.infer_kind(&items, SortKey::extract);
+        }
+        let entries: Vec<Entry> = items
+            .into_iter()
+            .map(|item| Entry {
+                row: keys::extract_row(&self.keys, &item),
+                item,
+            })
+            .collect();
+        self.extend_entries(entries);
+    }
+
+    /// Removes an element, found by identity, and returns whether it was
+    /// in the collection.
+    pub fn remove(&mut self, item: &JsValue) -> bool {
+        let row = keys::extract_row(&self.keys, item);
+        let range = self.equal_range(&row);
+        let position = self.entries[range.clone()]
+            .iter()
+            .position(|entry| Object::is(&entry.item, item))
+            .map(|offset| range.start + offset)
+            // The element's keys may have changed since it was inserted
+            .or_else(|| {
+                self.entries
+                    .iter()
+                    .position(|entry| Object::is(&entry.item, item))
+            });
+
+        match position {
+            Some(index) => {
+                self.entries.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes and returns the element at `index`, or `undefined` if the
+    /// index is out of range.
+    pub fn remove_at(&mut self, index: u32) -> JsValue {
+        let index = index as usize;
+        if index < self.entries.len() {
+            self.entries.remove(index).item
+        } else {
+            JsValue::UNDEFINED
+        }
+    }
+
+    /// Returns the element at `index` in sorted order, or `undefined`.
+    pub fn get(&self, index: u32) -> JsValue {
+        self.entries
+            .get(index as usize)
+            .map_or(JsValue::UNDEFINED, |entry| entry.item.clone())
+    }
+
+    /// Returns how many elements are ordered before `probe`, an object with
+    /// the leading key properties. Throws if the probe skips a key, like
+    /// `{ id: 5 }` with a spec of `"albumId, id"`, and throws a TypeError if
+    /// a probe value cannot be read as its key's type.
+    pub fn rank(&self, probe: &JsValue) -> Result<u32, JsValue> {
+        let bound = keys::extract_bound(&self.keys, probe)?;
+        Ok(self.lower_bound(&bound) as u32)
+    }
+
+    /// Returns the elements between two probes, both inclusive. Keys after
+    /// those a probe gives match every value, so with a spec of
+    /// `"albumId, id"`, `range({ albumId: 3 }, { albumId: 5 })` returns
+    /// albums 3 to 5. An `undefined` bound leaves that end open. Throws like
+    /// `rank` for a bad probe.
+    pub fn range(&self, lower: &JsValue, upper: &JsValue) -> Result<Array, JsValue> {
+        let start = if lower.is_undefined() {
+            0
+        } else {
+            self.lower_bound(&keys::extract_bound(&self.keys, lower)?)
+        };
+        let end = if upper.is_undefined() {
+            self.entries.len()
+        } else {
+            self.upper_bound(&keys::extract_bound(&self.keys, upper)?)
+        };
+        Ok(self.items(start..end.max(start)))
+    }
+
+    /// Returns up to `count` elements starting at position `start`, for
+    /// walking the collection in chunks.
+    pub fn chunk(&self, start: u32, count: u32) -> Array {
+        let len = self.entries.len();
+        let start = (start as usize).min(len);
+        let end = start.saturating_add(count as usize).min(len);
+        self.items(start..end)
+    }
+
+    pub fn to_array(&self) -> Array {
+        self.items(0..self.entries.len())
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::ProbeError;
+
+    fn collection() -> SortedCollection {
+        SortedCollection {
+            keys: vec![
+                SortKey::new("albumId").unwrap(),
+                SortKey::new("id").unwrap(),
+            ],
+            entries: Vec::new(),
+        }
+    }
+
+    fn entry(album: f64, id: f64) -> Entry {
+        Entry {
+            row: vec![KeyValue::Number(album), KeyValue::Number(id)],
+            item: JsValue::NULL,
+        }
+    }
+
+    fn ids(collection: &SortedCollection) -> Vec<f64> {
+        collection
+            .entries
+            .iter()
+            .map(|entry| match entry.row[1] {
+                KeyValue::Number(id) => id,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn inserts_keep_the_collection_ordered() {
+        let mut collection = collection();
+        assert_eq!(collection.insert_entry(entry(2.0, 1.0)), 0);
+        assert_eq!(collection.insert_entry(entry(1.0, 2.0)), 0);
+        assert_eq!(collection.insert_entry(entry(2.0, 0.0)), 1);
+        assert_eq!(collection.insert_entry(entry(3.0, 3.0)), 3);
+        collection.extend_entries([entry(1.0, 4.0), entry(2.0, 5.0)]);
+        assert_eq!(ids(&collection), [2.0, 4.0, 0.0, 1.0, 5.0, 3.0]);
+    }
+
+    #[test]
+    fn equal_keys_go_after_those_already_inserted() {
+        let mut collection = collection();
+        collection.keys.truncate(1);
+        assert_eq!(collection.insert_entry(entry(1.0, 0.0)), 0);
+        assert_eq!(collection.insert_entry(entry(1.0, 1.0)), 1);
+        assert_eq!(collection.insert_entry(entry(0.0, 2.0)), 0);
+        assert_eq!(collection.insert_entry(entry(1.0, 3.0)), 3);
+        collection.extend_entries([entry(1.0, 4.0), entry(0.0, 5.0)]);
+        assert_eq!(ids(&collection), [2.0, 5.0, 0.0, 1.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn a_partial_bound_matches_every_later_key() {
+        let mut collection = collection();
+        collection.extend_entries((0..6).map(|id| entry((id / 2) as f64, id as f64)));
+        let album = [KeyValue::Number(1.0)];
+        assert_eq!(collection.lower_bound(&album), 2);
+        assert_eq!(collection.upper_bound(&album), 4);
+        let exact = [KeyValue::Number(1.0), KeyValue::Number(3.0)];
+        assert_eq!(collection.lower_bound(&exact), 3);
+        assert_eq!(collection.upper_bound(&exact), 4);
+        assert_eq!(collection.lower_bound(&[]), 0);
+        assert_eq!(collection.upper_bound(&[]), 6);
+    }
+
+    #[test]
+    fn bad_probes_are_errors() {
+        let bound = keys::bound_from_probe(vec![Some(KeyValue::Number(1.0)), None]);
+        assert!(matches!(bound.as_deref(), Ok([KeyValue::Number(n)]) if *n == 1.0));
+        assert!(matches!(
+            keys::bound_from_probe(vec![None, Some(KeyValue::Number(1.0))]),
+            Err(ProbeError::SkippedKey {
+                present: 1,
+                missing: 0
+            })
+        ));
+        // A string probe for a numeric key reads as missing, which must not
+        // quietly shorten the bound to the keys before it
+        assert!(matches!(
+            keys::bound_from_probe(vec![Some(KeyValue::Number(1.0)), Some(KeyValue::Missing)]),
+            Err(ProbeError::WrongType(1))
+        ));
+    }
+}
@@ -0,0 +1,896 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+use js_sys::{JsString, Reflect};
+use wasm_bindgen::prelude::*;
+
+use crate::collation::StringOrder;
+use crate::lexer::{tokenize, SyntaxError, Token, TokenKind};
+
+/// A value produced while evaluating an expression. Strings borrow from the
+/// extracted fields where they can.
+#[derive(Clone, Debug)]
+pub enum Value<'a> {
+    Undefined,
+    Bool(bool),
+    Number(f64),
+    Text(Cow<'a, str>),
+}
+
+impl Value<'_> {
+    /// Reads a JS value. Anything that is not a number, string or boolean
+    /// becomes `Undefined`.
+    pub fn from_js(value: &JsValue) -> Value<'static> {
+        if let Some(n) = value.as_f64() {
+            Value::Number(n)
+        } else if let Some(s) = value.as_string() {
+            Value::Text(Cow::Owned(s))
+        } else if let Some(b) = value.as_bool() {
+            Value::Bool(b)
+        } else {
+            Value::Undefined
+        }
+    }
+
+    /// JS `ToNumber`.
+    pub fn to_number(&self) -> f64 {
+        match self {
+            Value::Undefined => f64::NAN,
+            Value::Bool(b) => f64::from(u8::from(*b)),
+            Value::Number(n) => *n,
+            Value::Text(s) => string_to_number(s),
+        }
+    }
+
+    /// JS truthiness.
+    pub fn truthy(&self) -> bool {
+        match self {
+            Value::Undefined => false,
+            Value::Bool(b) => *b,
+            Value::Number(n) => *n != 0.0 && !n.is_nan(),
+            Value::Text(s) => !s.is_empty(),
+        }
+    }
+
+    fn to_text(&self) -> Cow<'_, str> {
+        match self {
+            Value::Undefined => Cow::Borrowed("undefined"),
+            Value::Bool(b) => Cow::Borrowed(if *b { "true" } else { "false" }),
+            Value::Number(n) => Cow::Owned(number_to_string(*n)),
+            Value::Text(s) => Cow::Borrowed(s),
+        }
+    }
+
+    fn borrowed(&self) -> Value<'_> {
+        match self {
+            Value::Text(s) => Value::Text(Cow::Borrowed(s)),
+            Value::Undefined => Value::Undefined,
+            Value::Bool(b) => Value::Bool(*b),
+            Value::Number(n) => Value::Number(*n),
+        }
+    }
+}
+
+/// JS `ToNumber` applied to a string: decimal literals with an optional
+/// sign and exponent, `Infinity`, and unsigned `0x`, `0o` and `0b` integers,
+/// surrounded by optional whitespace. Anything else is NaN.
+fn string_to_number(text: &str) -> f64 {
+    let text = text.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}');
+    if text.is_empty() {
+        return 0.0;
+    }
+
+    let radix = match text.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0o" | "0O") => 8,
+        Some("0b" | "0B") => 2,
+        _ => 10,
+    };
+    if radix != 10 {
+        let digits = &text[2..];
+        if digits.is_empty() {
+            return f64::NAN;
+        }
+        return digits
+            .chars()
+            .try_fold(0.0, |total: f64, c| {
+                c.to_digit(radix)
+                    .map(|digit| total * f64::from(radix) + f64::from(digit))
+            })
+            .unwrap_or(f64::NAN);
+    }
+
+    let unsigned = text.strip_prefix(['+', '-']).unwrap_or(text);
+    if unsigned == "Infinity" {
+        return if text.starts_with('-') {
+            f64::NEG_INFINITY
+        } else {
+            f64::INFINITY
+        };
+    }
+    // Rust's parser also takes "inf", "infinity" and "nan" in any case,
+    // which JS does not
+    if !unsigned
+        .bytes()
+        .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'+' | b'-'))
+    {
+        return f64::NAN;
+    }
+    text.parse().unwrap_or(f64::NAN)
+}
+
+/// JS `Number.prototype.toString()`: the shortest digits that round-trip,
+/// in exponent form below 1e-6 and from 1e21 on, and `0` for -0.
+fn number_to_string(n: f64) -> String {
+    if n == 0.0 {
+        return "0".to_string();
+    }
+    if n.is_nan() {
+        return "NaN".to_string();
+    }
+    if n.is_infinite() {
+        return if n > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    let magnitude = n.abs();
+    if (1e-6..1e21).contains(&magnitude) {
+        return n.to_string();
+    }
+    // Rust writes `1.5e21` and `1e-7` where JS writes `1.5e+21` and `1e-7`
+    let text = format!("{:e}", n);
+    match text.split_once('e') {
+        Some((mantissa, exponent)) if !exponent.starts_with('-') => {
+            format!("{}e+{}", mantissa, exponent)
+        }
+        _ => text,
+    }
+}
+
+/// How deeply parentheses, unary operators, conditionals and method calls
+/// may nest.
+const MAX_DEPTH: usize = 256;
+
+#[derive(Clone, Copy, Debug)]
+enum Operator {
+    Or,
+    And,
+    Binary(BinaryOp),
+}
+
+/// The binary operators with their precedence levels, lowest first.
+const OPERATORS: &[(&str, usize, Operator)] = &[
+    ("||", 0, Operator::Or),
+    ("&&", 1, Operator::And),
+    ("===", 2, Operator::Binary(BinaryOp::Equal)),
+    ("!==", 2, Operator::Binary(BinaryOp::NotEqual)),
+    ("==", 2, Operator::Binary(BinaryOp::Equal)),
+    ("!=", 2, Operator::Binary(BinaryOp::NotEqual)),
+    ("<=", 3, Operator::Binary(BinaryOp::LessEqual)),
+    (">=", 3, Operator::Binary(BinaryOp::GreaterEqual)),
+    ("<", 3, Operator::Binary(BinaryOp::Less)),
+    (">", 3, Operator::Binary(BinaryOp::Greater)),
+    ("contains", 3, Operator::Binary(BinaryOp::Contains)),
+    ("startsWith", 3, Operator::Binary(BinaryOp::StartsWith)),
+    ("endsWith", 3, Operator::Binary(BinaryOp::EndsWith)),
+    ("+", 4, Operator::Binary(BinaryOp::Add)),
+    ("-", 4, Operator::Binary(BinaryOp::Sub)),
+    ("*", 5, Operator::Binary(BinaryOp::Mul)),
+    ("/", 5, Operator::Binary(BinaryOp::Div)),
+    ("%", 5, Operator::Binary(BinaryOp::Rem)),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+impl BinaryOp {
+    /// The precedence level in `OPERATORS`.
+    fn level(self) -> usize {
+        OPERATORS
+            .iter()
+            .find_map(|&(_, level, operator)| match operator {
+                Operator::Binary(op) if op == self => Some(level),
+                _ => None,
+            })
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug)]
+enum Expr {
+    Literal(Value<'static>),
+    /// A property path, stored in `Program::paths`, read from one of the
+    /// roots.
+    Field {
+        root: usize,
+        path: usize,
+    },
+    Negate(Box<Expr>),
+    Plus(Box<Expr>),
+    Not(Box<Expr>),
+    Length(Box<Expr>),
+    LocaleCompare(Box<Expr>, Box<Expr>),
+    /// A chain of left-associative operators of one precedence level, such
+    /// as `a - b + c`, kept flat so that long chains do not nest.
+    Binary(Box<Expr>, Vec<(BinaryOp, Expr)>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+/// A parsed expression over a small JavaScript subset: number and string
+/// literals, `true`, `false`, `undefined`, property paths on the roots,
+/// `.length`, `.localeCompare(...)`, arithmetic, comparisons, `!`, `&&`,
+/// `||` and `?:`, all with JS semantics, except that `==` and `!=` are
+/// strict like `===` and `!==`. On top of JS, `contains`, `startsWith` and
+/// `endsWith` test one string against another, and are false for anything
+/// that is not a string.
+///
+/// Every property path is extracted once per element with `extract`, so
+/// evaluation never calls back into JS. `a.title` and `b.title` share a
+/// path. An empty path stands for the element itself.
+#[derive(Debug)]
+pub struct Program {
+    expr: Expr,
+    paths: Vec<Vec<String>>,
+}
+
+impl Program {
+    /// Parses `source`, where `roots` are the names elements are bound to,
+    /// e.g. `["a", "b"]` for a comparator.
+    pub fn parse(source: &str, roots: &[&str]) -> Result<Program, SyntaxError> {
+        Program::parse_with(source, roots, false)
+    }
+
+    /// Parses an expression over a single element whose properties are
+    /// named directly, e.g. `albumId > 5 && title contains "quo"`. Names
+    /// that are not keywords read properties of the element.
+    pub fn parse_record(source: &str) -> Result<Program, SyntaxError> {
+        Program::parse_with(source, &[], true)
+    }
+
+    fn parse_with(source: &str, roots: &[&str], bare_fields: bool) -> Result<Program, SyntaxError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+            roots,
+            bare_fields,
+            paths: Vec::new(),
+            depth: 0,
+        };
+        let expr = parser.conditional()?;
+        let token = parser.peek();
+        if token.kind != TokenKind::End {
+            return Err(parser.unexpected(token));
+        }
+        Ok(Program {
+            expr,
+            paths: parser.paths,
+        })
+    }
+
+    /// Reads every property path the expression uses from one element.
+    pub fn extract(&self, item: &JsValue) -> Vec<Value<'static>> {
+        self.paths
+            .iter()
+            .map(|path| read_path(item, path))
+            .collect()
+    }
+
+    /// Evaluates the expression. `rows[r]` holds the values `extract`
+    /// returned for the element bound to root `r`, or for the one element
+    /// of a `parse_record` program; `order` is used by `localeCompare`.
+    pub fn eval<'a>(&self, rows: &[&'a [Value<'static>]], order: &StringOrder) -> Value<'a> {
+        Evaluator { rows, order }.eval(&self.expr)
+    }
+}
+
+/// Follows a property path. A string's `length` is read directly since
+/// `Reflect.get` only accepts objects.
+fn read_path(item: &JsValue, path: &[String]) -> Value<'static> {
+    let mut current = item.clone();
+    for segment in path {
+        if current.is_object() {
+            current =
+                Reflect::get(&current, &JsValue::from_str(segment)).unwrap_or(JsValue::UNDEFINED);
+        } else if segment == "length" && current.is_string() {
+            current = JsValue::from(JsString::from(current).length());
+        } else {
+            return Value::Undefined;
+        }
+    }
+    Value::from_js(&current)
+}
+
+struct Evaluator<'p, 'r, 'a> {
+    rows: &'r [&'a [Value<'static>]],
+    order: &'p StringOrder,
+}
+
+impl<'a> Evaluator<'_, '_, 'a> {
+    fn eval(&self, expr: &Expr) -> Value<'a> {
+        match expr {
+            Expr::Literal(value) => value.clone(),
+            Expr::Field { root, path } => self.rows[*root][*path].borrowed(),
+            Expr::Negate(operand) => Value::Number(-self.eval(operand).to_number()),
+            Expr::Plus(operand) => Value::Number(self.eval(operand).to_number()),
+            Expr::Not(operand) => Value::Bool(!self.eval(operand).truthy()),
+            Expr::Length(operand) => match self.eval(operand) {
+                Value::Text(s) => Value::Number(s.encode_utf16().count() as f64),
+                _ => Value::Undefined,
+            },
+            Expr::LocaleCompare(left, right) => {
+                let (left, right) = (self.eval(left), self.eval(right));
+                Value::Number(
+                    match self.order.compare(&left.to_text(), &right.to_text()) {
+                        Ordering::Less => -1.0,
+                        Ordering::Equal => 0.0,
+                        Ordering::Greater => 1.0,
+                    },
+                )
+            }
+            Expr::Binary(first, rest) => rest.iter().fold(self.eval(first), |left, (op, right)| {
+                binary(*op, left, self.eval(right))
+            }),
+            // The first falsy operand, or the last one
+            Expr::And(operands) => {
+                let (last, init) = operands.split_last().expect("`and` has operands");
+                init.iter()
+                    .map(|operand| self.eval(operand))
+                    .find(|value| !value.truthy())
+                    .unwrap_or_else(|| self.eval(last))
+            }
+            // The first truthy operand, or the last one
+            Expr::Or(operands) => {
+                let (last, init) = operands.split_last().expect("`or` has operands");
+                init.iter()
+                    .map(|operand| self.eval(operand))
+                    .find(Value::truthy)
+                    .unwrap_or_else(|| self.eval(last))
+            }
+            Expr::Conditional(condition, then, otherwise) => {
+                if self.eval(condition).truthy() {
+                    self.eval(then)
+                } else {
+                    self.eval(otherwise)
+                }
+            }
+        }
+    }
+}
+
+fn binary<'a>(op: BinaryOp, left: Value<'a>, right: Value<'a>) -> Value<'a> {
+    // Relational operators compare strings by UTF-16 code units when both
+    // sides are strings, and numbers otherwise
+    let relation = |left: &Value, right: &Value| match (left, right) {
+        (Value::Text(x), Value::Text(y)) => Some(x.encode_utf16().cmp(y.encode_utf16())),
+        _ => left.to_number().partial_cmp(&right.to_number()),
+    };
+    let strict_equal = |left: &Value, right: &Value| match (left, right) {
+        (Value::Undefined, Value::Undefined) => true,
+        (Value::Bool(x), Value::Bool(y)) => x == y,
+        (Value::Number(x), Value::Number(y)) => x == y,
+        (Value::Text(x), Value::Text(y)) => x == y,
+        _ => false,
+    };
+
+    match op {
+        BinaryOp::Add => match (&left, &right) {
+            (Value::Text(_), _) | (_, Value::Text(_)) => {
+                Value::Text(Cow::Owned(format!("{}{}", left.to_text(), right.to_text())))
+            }
+            _ => Value::Number(left.to_number() + right.to_number()),
+        },
+        BinaryOp::Sub => Value::Number(left.to_number() - right.to_number()),
+        BinaryOp::Mul => Value::Number(left.to_number() * right.to_number()),
+        BinaryOp::Div => Value::Number(left.to_number() / right.to_number()),
+        BinaryOp::Rem => Value::Number(left.to_number() % right.to_number()),
+        BinaryOp::Less => Value::Bool(relation(&left, &right) == Some(Ordering::Less)),
+        BinaryOp::Greater => Value::Bool(relation(&left, &right) == Some(Ordering::Greater)),
+        BinaryOp::LessEqual => Value::Bool(matches!(
+            relation(&left, &right),
+            Some(Ordering::Less | Ordering::Equal)
+        )),
+        BinaryOp::GreaterEqual => Value::Bool(matches!(
+            relation(&left, &right),
+            Some(Ordering::Greater | Ordering::Equal)
+        )),
+        BinaryOp::Equal => Value::Bool(strict_equal(&left, &right)),
+        BinaryOp::NotEqual => Value::Bool(!strict_equal(&left, &right)),
+        BinaryOp::Contains | BinaryOp::StartsWith | BinaryOp::EndsWith => {
+            let (Value::Text(x), Value::Text(y)) = (&left, &right) else {
+                return Value::Bool(false);
+            };
+            Value::Bool(match op {
+                BinaryOp::Contains => x.contains(y.as_ref()),
+                BinaryOp::StartsWith => x.starts_with(y.as_ref()),
+                _ => x.ends_with(y.as_ref()),
+            })
+        }
+    }
+}
+
+/// Recursive descent parser, with the binary operators parsed by
+/// precedence climbing.
+struct Parser<'r> {
+    tokens: Vec<Token>,
+    position: usize,
+    roots: &'r [&'r str],
+    /// Whether unknown names are properties of an implicit single root.
+    bare_fields: bool,
+    paths: Vec<Vec<String>>,
+    /// How deeply the expression parsed so far nests, see `nested`.
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Token {
+        self.tokens[self.position].clone()
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.peek();
+        if token.kind != TokenKind::End {
+            self.position += 1;
+        }
+        token
+    }
+
+    /// Consumes the next token if it is the punctuation `punct`.
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.peek().kind, TokenKind::Punct(p) if p == punct) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), SyntaxError> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            let token = self.peek();
+            Err(SyntaxError::new(
+                format!("expected '{}' but found {}", punct, describe(&token)),
+                token.offset,
+                token.len,
+            ))
+        }
+    }
+
+    fn unexpected(&self, token: Token) -> SyntaxError {
+        SyntaxError::new(
+            format!("unexpected {}", describe(&token)),
+            token.offset,
+            token.len,
+        )
+    }
+
+    /// Runs `parse` one nesting level deeper. Parsing, evaluating and
+    /// dropping an expression all recurse once per level, so the depth is
+    /// capped to keep deep expressions from overflowing the stack, which
+    /// would trap the module.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, SyntaxError>,
+    ) -> Result<T, SyntaxError> {
+        if self.depth >= MAX_DEPTH {
+            let token = self.peek();
+            return Err(SyntaxError::new(
+                "expression nested too deeply",
+                token.offset,
+                token.len,
+            ));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn field(&mut self, root: usize, path: Vec<String>) -> Expr {
+        let path = match self.paths.iter().position(|existing| *existing == path) {
+            Some(index) => index,
+            None => {
+                self.paths.push(path);
+                self.paths.len() - 1
+            }
+        };
+        Expr::Field { root, path }
+    }
+
+    fn conditional(&mut self) -> Result<Expr, SyntaxError> {
+        let condition = self.binary(0)?;
+        if !self.eat("?") {
+            return Ok(condition);
+        }
+        let then = self.nested(Self::conditional)?;
+        self.expect(":")?;
+        let otherwise = self.nested(Self::conditional)?;
+        Ok(Expr::Conditional(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    /// The operator the next token is, if any, with its precedence level.
+    fn peek_operator(&self) -> Option<(usize, Operator)> {
+        let token = self.peek();
+        OPERATORS
+            .iter()
+            .find(|(name, _, _)| match &token.kind {
+                TokenKind::Punct(punct) => punct == name,
+                // Word operators start with a letter
+                TokenKind::Ident(word) => word == name,
+                _ => false,
+            })
+            .map(|&(_, level, operator)| (level, operator))
+    }
+
+    /// Parses the binary operators from `min_level` up by precedence
+    /// climbing. Operators of one level chain into a single flat node, so
+    /// neither long chains nor the number of levels deepen the recursion.
+    fn binary(&mut self, min_level: usize) -> Result<Expr, SyntaxError> {
+        let mut left = self.unary()?;
+        while let Some((level, operator)) = self.peek_operator() {
+            if level < min_level {
+                break;
+            }
+            self.position += 1;
+            let right = self.binary(level + 1)?;
+            left = match (operator, left) {
+                (Operator::Or, Expr::Or(mut operands)) => {
+                    operands.push(right);
+                    Expr::Or(operands)
+                }
+                (Operator::Or, left) => Expr::Or(vec![left, right]),
+                (Operator::And, Expr::And(mut operands)) => {
+                    operands.push(right);
+                    Expr::And(operands)
+                }
+                (Operator::And, left) => Expr::And(vec![left, right]),
+                (Operator::Binary(op), Expr::Binary(first, mut rest))
+                    if rest[0].0.level() == level =>
+                {
+                    rest.push((op, right));
+                    Expr::Binary(first, rest)
+                }
+                (Operator::Binary(op), left) => Expr::Binary(Box::new(left), vec![(op, right)]),
+            };
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, SyntaxError> {
+        if self.eat("-") {
+            Ok(Expr::Negate(Box::new(self.nested(Self::unary)?)))
+        } else if self.eat("+") {
+            Ok(Expr::Plus(Box::new(self.nested(Self::unary)?)))
+        } else if self.eat("!") {
+            Ok(Expr::Not(Box::new(self.nested(Self::unary)?)))
+        } else {
+            self.postfix()
+        }
+    }
+
+    /// A primary expression followed by property accesses and method calls.
+    /// Property accesses directly on a root extend its path, so `a.b.c` is
+    /// a single field.
+    fn postfix(&mut self) -> Result<Expr, SyntaxError> {
+        let token = self.next();
+        let mut path = None;
+        let mut expr = match &token.kind {
+            TokenKind::Number(n) => Expr::Literal(Value::Number(*n)),
+            TokenKind::Str(s) => Expr::Literal(Value::Text(Cow::Owned(s.clone()))),
+            TokenKind::Ident(name) => match name.as_str() {
+                "true" => Expr::Literal(Value::Bool(true)),
+                "false" => Expr::Literal(Value::Bool(false)),
+                "undefined" | "null" => Expr::Literal(Value::Undefined),
+                "NaN" => Expr::Literal(Value::Number(f64::NAN)),
+                "Infinity" => Expr::Literal(Value::Number(f64::INFINITY)),
+                _ => match self.roots.iter().position(|root| root == name) {
+                    Some(root) => {
+                        path = Some((root, Vec::new()));
+                        Expr::Literal(Value::Undefined)
+                    }
+                    None if self.bare_fields => {
+                        path = Some((0, vec![name.clone()]));
+                        Expr::Literal(Value::Undefined)
+                    }
+                    None => {
+                        return Err(SyntaxError::new(
+                            format!(
+                                "unknown name '{}', expected one of {}",
+                                name,
+                                self.roots.join(", ")
+                            ),
+                            token.offset,
+                            token.len,
+                        ))
+                    }
+                },
+            },
+            TokenKind::Punct("(") => {
+                let inner = self.nested(Self::conditional)?;
+                self.expect(")")?;
+                inner
+            }
+            _ => return Err(self.unexpected(token)),
+        };
+
+        // Each method call or `.length` wraps the expression once more
+        let mut wrapped = 0;
+        while self.eat(".") {
+            let token = self.next();
+            if self.depth + wrapped >= MAX_DEPTH {
+                return Err(SyntaxError::new(
+                    "expression nested too deeply",
+                    token.offset,
+                    token.len,
+                ));
+            }
+            let TokenKind::Ident(name) = token.kind else {
+                return Err(SyntaxError::new(
+                    format!("expected a property name but found {}", describe(&token)),
+                    token.offset,
+                    token.len,
+                ));
+            };
+
+            if self.eat("(") {
+                if let Some((root, segments)) = path.take() {
+                    expr = self.field(root, segments);
+                }
+                if name != "localeCompare" {
+                    return Err(SyntaxError::new(
+                        format!("unknown method '{}', only localeCompare is supported", name),
+                        token.offset,
+                        token.len,
+                    ));
+                }
+                let argument = self.nested(Self::conditional)?;
+                self.expect(")")?;
+                expr = Expr::LocaleCompare(Box::new(expr), Box::new(argument));
+                wrapped += 1;
+            } else if let Some((_, segments)) = path.as_mut() {
+                segments.push(name);
+            } else if name == "length" {
+                expr = Expr::Length(Box::new(expr));
+                wrapped += 1;
+            } else {
+                return Err(SyntaxError::new(
+                    format!("cannot read property '{}' of a computed value", name),
+                    token.offset,
+                    token.len,
+                ));
+            }
+        }
+
+        if let Some((root, segments)) = path {
+            expr = self.field(root, segments);
+        }
+        Ok(expr)
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match &token.kind {
+        TokenKind::Number(n) => format!("number {}", n),
+        TokenKind::Str(s) => format!("string '{}'", s),
+        TokenKind::Ident(name) => format!("'{}'", name),
+        TokenKind::Punct(punct) => format!("'{}'", punct),
+        TokenKind::End => "end of expression".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(text: &str) -> f64 {
+        Value::Text(Cow::Borrowed(text)).to_number()
+    }
+
+    fn text(n: f64) -> String {
+        Value::Number(n).to_text().into_owned()
+    }
+
+    #[test]
+    fn strings_convert_to_numbers_like_js() {
+        assert_eq!(number(""), 0.0);
+        assert_eq!(number("  \n"), 0.0);
+        assert_eq!(number(" 42 "), 42.0);
+        assert_eq!(number("-1.5e3"), -1500.0);
+        assert_eq!(number(".5"), 0.5);
+        assert_eq!(number("5."), 5.0);
+        assert_eq!(number("1.e2"), 100.0);
+        assert_eq!(number("+Infinity"), f64::INFINITY);
+        assert_eq!(number("-Infinity"), f64::NEG_INFINITY);
+        assert_eq!(number("0x10"), 16.0);
+        assert_eq!(number("0o17"), 15.0);
+        assert_eq!(number("0B101"), 5.0);
+        for invalid in [
+            "inf", "infinity", "NaN", "nan", "-0x10", "0x", "1e", ".", "1_000", "12px",
+        ] {
+            assert!(number(invalid).is_nan(), "{:?} should be NaN", invalid);
+        }
+    }
+
+    #[test]
+    fn numbers_convert_to_strings_like_js() {
+        assert_eq!(text(0.0), "0");
+        assert_eq!(text(-0.0), "0");
+        assert_eq!(text(1.5), "1.5");
+        assert_eq!(text(-2.0), "-2");
+        assert_eq!(text(f64::NAN), "NaN");
+        assert_eq!(text(f64::NEG_INFINITY), "-Infinity");
+        assert_eq!(text(1e20), "100000000000000000000");
+        assert_eq!(text(1e21), "1e+21");
+        assert_eq!(text(-1.5e300), "-1.5e+300");
+        assert_eq!(text(0.000001), "0.000001");
+        assert_eq!(text(1.5e-7), "1.5e-7");
+    }
+
+    /// Evaluates a comparator expression over two elements given as
+    /// `(path, value)` fields, the way `extract` would read them.
+    fn eval_pair(
+        source: &str,
+        a: &[(&str, Value<'static>)],
+        b: &[(&str, Value<'static>)],
+    ) -> String {
+        let program = Program::parse(source, &["a", "b"]).unwrap();
+        let row = |fields: &[(&str, Value<'static>)]| -> Vec<Value<'static>> {
+            program
+                .paths
+                .iter()
+                .map(|path| {
+                    fields
+                        .iter()
+                        .find(|(name, _)| *name == path.join("."))
+                        .map_or(Value::Undefined, |(_, value)| value.clone())
+                })
+                .collect()
+        };
+        let (a, b) = (row(a), row(b));
+        program
+            .eval(&[&a, &b], &StringOrder::Binary)
+            .to_text()
+            .into_owned()
+    }
+
+    #[test]
+    fn evaluates_with_js_semantics() {
+        let a = [
+            ("n", Value::Number(3.0)),
+            ("s", Value::Text(Cow::Borrowed("Set 10"))),
+            ("s.length", Value::Number(6.0)),
+        ];
+        let b = [
+            ("n", Value::Number(5.0)),
+            ("s", Value::Text(Cow::Borrowed("Set 2"))),
+        ];
+        let cases = [
+            ("a.n - b.n", "-2"),
+            ("1 + 2 * 3 % 4", "3"),
+            ("(1 + 2) * 3", "9"),
+            ("a.n + a.s", "3Set 10"),
+            ("a.s < b.s", "true"),
+            ("a.s.length", "6"),
+            ("(a.s + b.s).length", "11"),
+            ("a.s.localeCompare(b.s)", "-1"),
+            ("-a.s", "NaN"),
+            ("+'0x10' + 1", "17"),
+            ("1 / 0", "Infinity"),
+            ("0 * -1", "0"),
+            ("a.n == 3 ? 'yes' : 'no'", "yes"),
+            ("a.n && b.n", "5"),
+            ("0 || undefined", "undefined"),
+            ("!a.missing", "true"),
+            ("'3' == 3", "false"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(eval_pair(source, &a, &b), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn reports_syntax_errors_where_they_occur() {
+        let error = Program::parse("a.id - - ", &["a", "b"]).unwrap_err();
+        assert_eq!(error.offset, 9);
+        let error = Program::parse("a.id b.id", &["a", "b"]).unwrap_err();
+        assert_eq!((error.offset, error.len), (5, 1));
+        assert!(Program::parse("c.id", &["a", "b"]).is_err());
+        assert!(Program::parse("(a.id", &["a", "b"]).is_err());
+        assert!(Program::parse("", &["a", "b"]).is_err());
+    }
+
+    #[test]
+    fn string_operators_test_substrings() {
+        let a = [("s", Value::Text(Cow::Borrowed("Set 10")))];
+        let b = [("s", Value::Text(Cow::Borrowed("Set 2")))];
+        let cases = [
+            ("a.s contains 'Set' && b.s endsWith '2'", "true"),
+            ("a.s startsWith 'set'", "false"),
+            ("a.s contains ''", "true"),
+            ("a.n contains 'x'", "false"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(eval_pair(source, &a, &b), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn record_expressions_read_bare_fields() {
+        let program = Program::parse_record("albumId > 5 && title startsWith \"quo\"").unwrap();
+        assert_eq!(program.paths, [["albumId"], ["title"]]);
+        let row = [Value::Number(6.0), Value::Text(Cow::Borrowed("quod"))];
+        assert!(program.eval(&[&row], &StringOrder::Binary).truthy());
+        let row = [Value::Number(6.0), Value::Undefined];
+        assert!(!program.eval(&[&row], &StringOrder::Binary).truthy());
+    }
+
+    #[test]
+    fn rejects_expressions_nested_too_deeply() {
+        let roots = ["a", "b"];
+        let parens = format!("{}a.id{}", "(".repeat(100_000), ")".repeat(100_000));
+        let negations = format!("{}a.id", "-".repeat(100_000));
+        let conditionals = "a.id ? 1 : ".repeat(100_000) + "0";
+        let lengths = format!("(a.s){}", ".length".repeat(100_000));
+        for source in [parens, negations, conditionals, lengths] {
+            let error = Program::parse(&source, &roots).unwrap_err();
+            assert_eq!(error.message, "expression nested too deeply");
+        }
+
+        let nested = |depth: usize| format!("{}a.id{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Program::parse(&nested(MAX_DEPTH - 1), &roots).is_ok());
+        assert!(Program::parse(&nested(MAX_DEPTH), &roots).is_err());
+    }
+
+    #[test]
+    fn long_flat_chains_do_not_nest() {
+        let terms = 100_000;
+        let a = [("n", Value::Number(f64::from(terms - 1)))];
+        let or = (0..terms)
+            .map(|i| format!("a.n == {}", i))
+            .collect::<Vec<_>>()
+            .join(" || ");
+        assert_eq!(eval_pair(&or, &a, &[]), "true");
+        let and = vec!["a.n"; terms as usize].join(" && ");
+        assert_eq!(eval_pair(&and, &a, &[]), "99999");
+        let sum = vec!["1"; terms as usize].join(" + ") + " - 1 * 2";
+        assert_eq!(eval_pair(&sum, &a, &[]), "99998");
+        assert_eq!(eval_pair("0 || '' || undefined", &a, &[]), "undefined");
+        assert_eq!(eval_pair("1 && 'x' && 0 && 2", &a, &[]), "0");
+    }
+
+    #[test]
+    fn deeply_nested_filters_are_syntax_errors() {
+        // What `filter_by_expression` parses
+        let source = format!("{}albumId > 5{}", "!(".repeat(50_000), ")".repeat(50_000));
+        let error = Program::parse_record(&source).unwrap_err();
+        assert_eq!(error.message, "expression nested too deeply");
+
+        let source = (0..50_000)
+            .map(|i| format!("id == {}", i))
+            .collect::<Vec<_>>()
+            .join(" || ");
+        let program = Program::parse_record(&source).unwrap();
+        let row = [Value::Number(49_999.0)];
+        assert!(program.eval(&[&row], &StringOrder::Binary).truthy());
+    }
+}
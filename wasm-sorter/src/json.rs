@@ -0,0 +1,340 @@
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+use crate::algorithms::{SortAlgorithm, SortStats};
+use crate::js_error;
+use crate::keys::{BigKey, KeyKind, KeyTable, KeyValue, SortKey};
+
+/// Follows a key's property path through parsed JSON. Array elements are
+/// reached by index, as `Reflect.get` would.
+fn lookup<'a>(key: &SortKey, item: &'a Value) -> Option<&'a Value> {
+    key.path
+        .iter()
+        .try_fold(item, |current, segment| match current {
+            Value::Object(map) => map.get(segment),
+            Value::Array(values) => values.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+/// The JSON counterpart of `SortKey::extract`. JSON has no dates or
+/// BigInts, so date keys read epoch milliseconds or date strings, and
+/// BigInt keys read integers, which serde_json keeps exact up to 64 bits.
+/// Date strings in the ISO 8601 format of `Date.prototype.toJSON` are
+/// parsed here; only other strings are handed to `Date.parse`.
+pub fn extract(key: &SortKey, item: &Value) -> KeyValue {
+    let Some(value) = lookup(key, item) else {
+        return KeyValue::Missing;
+    };
+    let extracted = match key.kind {
+        KeyKind::Number => value.as_f64().map(KeyValue::Number),
+        KeyKind::String => value.as_str().map(|text| KeyValue::Text(text.to_string())),
+        KeyKind::Date => match value {
+            Value::String(text) => {
+                let time = parse_iso_date(text).unwrap_or_else(|| js_sys::Date::parse(text));
+                (!time.is_nan()).then_some(KeyValue::Date(time))
+            }
+            _ => value.as_f64().map(KeyValue::Date),
+        },
+        KeyKind::BigInt => match value {
+            Value::Number(n) if n.is_i64() || n.is_u64() => {
+                Some(KeyValue::BigInt(BigKey::parse(&n.to_string())))
+            }
+            _ => value
+                .as_f64()
+                .and_then(BigKey::from_integer)
+                .map(KeyValue::BigInt),
+        },
+        KeyKind::Boolean => value.as_bool().map(KeyValue::Bool),
+        KeyKind::Mixed => match value {
+            Value::Number(n) => n.as_f64().map(KeyValue::Number),
+            Value::String(text) => Some(KeyValue::Text(text.clone())),
+            Value::Bool(b) => Some(KeyValue::Bool(*b)),
+            _ => None,
+        },
+    };
+    extracted.unwrap_or(KeyValue::Missing)
+}
+
+/// Parses the date time string format of ECMAScript in UTC, e.g.
+/// `2024-03-01`, `2024-03-01T12:30:00.000Z` or `+010000-01-01T00:00+02:00`,
+/// returning epoch milliseconds, or NaN for a date that does not exist.
+/// Returns `None` for anything else, including date-times without an
+/// offset, which JS reads in the local time zone.
+fn parse_iso_date(text: &str) -> Option<f64> {
+    let mut cursor = Cursor {
+        bytes: text.as_bytes(),
+        position: 0,
+    };
+
+    let year = if cursor.eat(b'+') {
+        cursor.number(6)?
+    } else if cursor.eat(b'-') {
+        match cursor.number(6)? {
+            0 => return Some(f64::NAN),
+            year => -year,
+        }
+    } else {
+        cursor.number(4)?
+    };
+    let (mut month, mut day) = (1, 1);
+    if cursor.eat(b'-') {
+        month = cursor.number(2)?;
+        if cursor.eat(b'-') {
+            day = cursor.number(2)?;
+        }
+    }
+
+    let (mut hour, mut minute, mut second, mut millis, mut offset) = (0, 0, 0, 0, 0);
+    if cursor.eat(b'T') {
+        hour = cursor.number(2)?;
+        cursor.expect(b':')?;
+        minute = cursor.number(2)?;
+        if cursor.eat(b':') {
+            second = cursor.number(2)?;
+            if cursor.eat(b'.') {
+                // Digits past milliseconds are dropped, as V8 does
+                let digits = cursor.digits();
+                if digits.is_empty() {
+                    return None;
+                }
+                millis = digits
+                    .iter()
+                    .chain(b"00")
+                    .take(3)
+                    .fold(0, |n, &d| n * 10 + i64::from(d - b'0'));
+            }
+        }
+
+        offset = if cursor.eat(b'Z') {
+            0
+        } else {
+            let sign = if cursor.eat(b'+') {
+                1
+            } else {
+                cursor.expect(b'-')?;
+                -1
+            };
+            let hours = cursor.number(2)?;
+            cursor.expect(b':')?;
+            let minutes = cursor.number(2)?;
+            if hours > 23 || minutes > 59 {
+                return Some(f64::NAN);
+            }
+            sign * (hours * 60 + minutes)
+        };
+    }
+    if cursor.position != cursor.bytes.len() {
+        return None;
+    }
+
+    let days_in_month = match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    let end_of_day = hour == 24 && minute == 0 && second == 0 && millis == 0;
+    if !(1..=12).contains(&month)
+        || !(1..=days_in_month).contains(&day)
+        || (hour > 23 && !end_of_day)
+        || minute > 59
+        || second > 59
+    {
+        return Some(f64::NAN);
+    }
+
+    let minutes = (days_from_civil(year, month, day) * 24 + hour) * 60 + minute - offset;
+    let time = ((minutes * 60 + second) * 1000 + millis) as f64;
+    // The range of a JS `Date`
+    Some(if time.abs() <= 8.64e15 {
+        time
+    } else {
+        f64::NAN
+    })
+}
+
+/// Reads the fields of a date string.
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Cursor<'_> {
+    fn eat(&mut self, byte: u8) -> bool {
+        let found = self.bytes.get(self.position) == Some(&byte);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.eat(byte).then_some(())
+    }
+
+    /// Reads a run of digits, possibly empty.
+    fn digits(&mut self) -> &[u8] {
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(u8::is_ascii_digit)
+        {
+            self.position += 1;
+        }
+        &self.bytes[start..self.position]
+    }
+
+    /// Reads a number of exactly `len` digits.
+    fn number(&mut self, len: usize) -> Option<i64> {
+        let field = self.bytes.get(self.position..self.position + len)?;
+        if !field.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.position += len;
+        Some(field.iter().fold(0, |n, &d| n * 10 + i64::from(d - b'0')))
+    }
+}
+
+/// Days from 1970-01-01 to a date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    // Years start in March so the leap day ends them
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Parses a JSON array, sorts its elements by `keys` and serializes the
+/// result. Elements are moved, never re-encoded field by field, so the
+/// output keeps their property order.
+pub fn sort(
+    json: &str,
+    keys: Vec<SortKey>,
+    algorithm: SortAlgorithm,
+    stable: bool,
+    stats: &mut SortStats,
+) -> Result<String, JsValue> {
+    let parsed: Value =
+        serde_json::from_str(json).map_err(|err| js_error(&format!("invalid JSON: {}", err)))?;
+    let Value::Array(mut items) = parsed else {
+        return Err(js_error("JSON input must be an array"));
+    };
+
+    let table = KeyTable::build_with(keys, &items, extract);
+    let indices = table.sorted_indices(algorithm, stable, stats);
+    let sorted: Vec<Value> = indices
+        .into_iter()
+        .map(|index| items[index].take())
+        .collect();
+
+    serde_json::to_string(&sorted).map_err(|err| js_error(&err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sort_by(json: &str, paths: &[&str]) -> String {
+        let keys = paths
+            .iter()
+            .map(|path| SortKey::new(path).unwrap())
+            .collect();
+        sort(json, keys, SortAlgorithm::Auto, true, &mut SortStats::new()).unwrap()
+    }
+
+    #[test]
+    fn untyped_keys_take_the_type_of_the_first_value() {
+        assert_eq!(
+            sort_by(r#"[{"t":"b"},{},{"t":"a"},{"t":3}]"#, &["t"]),
+            r#"[{"t":"a"},{"t":"b"},{},{"t":3}]"#
+        );
+        assert_eq!(
+            sort_by(r#"[{"n":10},{"n":"1"},{"n":9}]"#, &["n"]),
+            r#"[{"n":9},{"n":10},{"n":"1"}]"#
+        );
+        assert_eq!(
+            sort_by(
+                r#"[{"a":2,"b":true},{"a":1},{"a":2,"b":false}]"#,
+                &["a", "b"]
+            ),
+            r#"[{"a":1},{"a":2,"b":false},{"a":2,"b":true}]"#
+        );
+        assert_eq!(sort_by(r#"[{},{"x":1}]"#, &["y"]), r#"[{},{"x":1}]"#);
+    }
+
+    #[test]
+    fn typed_keys_are_not_inferred() {
+        let mut key = SortKey::new("t").unwrap();
+        key.kind = KeyKind::Number;
+        key.typed = true;
+        let items: Vec<Value> = serde_json::from_str(r#"[{"t":"a"}]"#).unwrap();
+        key.infer_kind(&items, extract);
+        assert!(key.kind == KeyKind::Number);
+
+        let mut key = SortKey::new("t").unwrap();
+        key.infer_kind(&items, extract);
+        assert!(key.kind == KeyKind::String && key.typed);
+    }
+
+    #[test]
+    fn parses_iso_dates_in_utc() {
+        let cases = [
+            ("1970-01-01", 0.0),
+            ("1970-01-01T00:00:00.000Z", 0.0),
+            ("2024-03-01T12:30:15.250Z", 1_709_296_215_250.0),
+            ("2024-02-29", 1_709_164_800_000.0),
+            ("2024", 1_704_067_200_000.0),
+            ("2024-03", 1_709_251_200_000.0),
+            ("2024-03-01T12:30+02:00", 1_709_289_000_000.0),
+            ("2024-03-01T12:30:15.2509Z", 1_709_296_215_250.0),
+            ("1969-12-31T23:59:59.999Z", -1.0),
+            ("2024-03-01T24:00Z", 1_709_337_600_000.0),
+            ("-000001-01-01", -62_198_755_200_000.0),
+            ("+275760-09-13", 8.64e15),
+            ("0000-01-01", -62_167_219_200_000.0),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_iso_date(text), Some(expected), "{}", text);
+        }
+    }
+
+    #[test]
+    fn dates_that_do_not_exist_are_nan() {
+        for text in [
+            "2023-02-29",
+            "2024-13-01",
+            "2024-04-31",
+            "2024-03-01T24:01Z",
+            "2024-03-01T12:60Z",
+            "2024-03-01T12:00+24:00",
+            "-000000-01-01",
+            "+275760-09-14",
+        ] {
+            assert!(parse_iso_date(text).unwrap().is_nan(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn leaves_other_formats_to_date_parse() {
+        for text in [
+            "",
+            "2024-03-01T12:30",
+            "2024-03-01T12:30:15",
+            "2024-3-1",
+            "2024/03/01",
+            "March 1, 2024",
+            "2024-03-01 12:30Z",
+            "2024-03-01T12Z",
+            "2024-03-01T12:30:15.Z",
+            "2024-03-01T12:30+0200",
+            "2024-03-01Z",
+            "20240301",
+        ] {
+            assert_eq!(parse_iso_date(text), None, "{}", text);
+        }
+    }
+}
@@ -0,0 +1,778 @@
+use std::cmp::Ordering;
+
+use js_sys::{Array, BigInt, Date, Reflect};
+use wasm_bindgen::prelude::*;
+
+use crate::algorithms::{self, SortAlgorithm, SortStats};
+use crate::collation::StringOrder;
+use crate::numeric::FloatOrder;
+use crate::options::get_string;
+use crate::{js_error, js_type_error};
+
+/// How a key's values are read from each element.
+#[derive(Clone, Copy, PartialEq)]
+pub enum KeyKind {
+    Number,
+    String,
+    /// `Date` objects by their timestamp, or numbers as epoch milliseconds.
+    Date,
+    /// BigInts, or numbers that are integers, compared exactly.
+    BigInt,
+    Boolean,
+    /// Any of the above, ordered booleans, then numbers and BigInts by
+    /// value, then dates, then strings.
+    Mixed,
+}
+
+impl KeyKind {
+    /// The kind that reads `value`, or `None` if it is missing.
+    fn of(value: &KeyValue) -> Option<KeyKind> {
+        match value {
+            KeyValue::Missing => None,
+            KeyValue::Number(_) => Some(KeyKind::Number),
+            KeyValue::Text(_) => Some(KeyKind::String),
+            KeyValue::Date(_) => Some(KeyKind::Date),
+            KeyValue::BigInt(_) => Some(KeyKind::BigInt),
+            KeyValue::Bool(_) => Some(KeyKind::Boolean),
+        }
+    }
+
+    /// The `type` that selects this kind in a specification.
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyKind::Number => "number",
+            KeyKind::String => "string",
+            KeyKind::Date => "date",
+            KeyKind::BigInt => "bigint",
+            KeyKind::Boolean => "boolean",
+            KeyKind::Mixed => "mixed",
+        }
+    }
+}
+
+/// One entry of a sort specification, e.g. `{ path: "albumId", direction: "desc" }`.
+pub struct SortKey {
+    pub path: Vec<String>,
+    pub descending: bool,
+    pub kind: KeyKind,
+    /// Whether `kind` is settled, by the specification's `type` or by
+    /// `infer_kind`. Until then it is the default, `Number`.
+    pub typed: bool,
+    pub nulls_first: bool,
+    pub order: StringOrder,
+    pub numbers: FloatOrder,
+}
+
+/// A key extracted from one element. Values that are absent or of the wrong
+/// type become `Missing`; where they go is decided by `nulls_first`, not by
+/// the key's direction.
+pub enum KeyValue {
+    Missing,
+    Number(f64),
+    Text(String),
+    Date(f64),
+    BigInt(BigKey),
+    Bool(bool),
+}
+
+impl KeyValue {
+    /// Position of the value's type in the `KeyKind::Mixed` order.
+    fn type_rank(&self) -> u8 {
+        match self {
+            KeyValue::Bool(_) => 0,
+            KeyValue::Number(_) | KeyValue::BigInt(_) => 1,
+            KeyValue::Date(_) => 2,
+            KeyValue::Text(_) => 3,
+            KeyValue::Missing => 4,
+        }
+    }
+}
+
+/// A BigInt key as a sign and decimal digits, so that values of any size
+/// compare exactly.
+pub struct BigKey {
+    negative: bool,
+    digits: String,
+}
+
+impl BigKey {
+    fn from_js(value: &JsValue) -> Option<BigKey> {
+        let text = String::from(value.dyn_ref::<BigInt>()?.to_string(10).ok()?);
+        Some(BigKey::parse(&text))
+    }
+
+    /// Converts a number if it is an integer. `{:.0}` writes every digit
+    /// where `Display` would not.
+    pub fn from_integer(n: f64) -> Option<BigKey> {
+        (n.is_finite() && n.fract() == 0.0).then(|| BigKey::parse(&format!("{:.0}", n)))
+    }
+
+    /// Reads an optionally signed string of decimal digits.
+    pub fn parse(text: &str) -> BigKey {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text),
+        };
+        let digits = digits.trim_start_matches('0');
+        BigKey {
+            // Zero has no sign, and -0 as a number has to match it
+            negative: negative && !digits.is_empty(),
+            digits: digits.to_string(),
+        }
+    }
+}
+
+impl Ord for BigKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Without leading zeros a longer magnitude is a larger one
+        let magnitude = self
+            .digits
+            .len()
+            .cmp(&other.digits.len())
+            .then_with(|| self.digits.cmp(&other.digits));
+        match (self.negative, other.negative) {
+            (false, false) => magnitude,
+            (true, true) => magnitude.reverse(),
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for BigKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for BigKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for BigKey {}
+
+fn parse_direction(direction: &str) -> Result<bool, JsValue> {
+    match direction {
+        "asc" => Ok(false),
+        "desc" => Ok(true),
+        other => Err(js_error(&format!(
+            "unknown sort direction '{}', expected 'asc' or 'desc'",
+            other
+        ))),
+    }
+}
+
+/// Applies a key type. `"natural"` is shorthand for a string key compared
+/// with `StringOrder::Natural`.
+fn set_type(key: &mut SortKey, kind: &str) -> Result<(), JsValue> {
+    match kind {
+        "number" => key.kind = KeyKind::Number,
+        "string" => key.kind = KeyKind::String,
+        "natural" => {
+            key.kind = KeyKind::String;
+            key.order = StringOrder::Natural;
+        }
+        "date" => key.kind = KeyKind::Date,
+        "bigint" => key.kind = KeyKind::BigInt,
+        "boolean" => key.kind = KeyKind::Boolean,
+        "mixed" => key.kind = KeyKind::Mixed,
+        other => {
+            return Err(js_error(&format!(
+                "unknown key type '{}', expected 'number', 'string', 'natural', \
+                 'date', 'bigint', 'boolean' or 'mixed'",
+                other
+            )))
+        }
+    }
+    key.typed = true;
+    Ok(())
+}
+
+fn parse_nulls(nulls: &str) -> Result<bool, JsValue> {
+    match nulls {
+        "first" => Ok(true),
+        "last" => Ok(false),
+        other => Err(js_error(&format!(
+            "unknown null placement '{}', expected 'first' or 'last'",
+            other
+        ))),
+    }
+}
+
+impl SortKey {
+    fn from_js(entry: &JsValue) -> Result<Vec<SortKey>, JsValue> {
+        if let Some(text) = entry.as_string() {
+            return parse_clauses(&text);
+        }
+        if !entry.is_object() {
+            return Err(js_error("sort key must be a string or an object"));
+        }
+
+        let path =
+            get_string(entry, "path")?.ok_or_else(|| js_error("sort key is missing a 'path'"))?;
+        let mut key = SortKey::new(&path)?;
+        if let Some(direction) = get_string(entry, "direction")? {
+            key.descending = parse_direction(&direction)?;
+        }
+        key.apply_options(entry)?;
+
+        Ok(vec![key])
+    }
+
+    /// Reads `type`, `nulls` and the string and number ordering options.
+    fn apply_options(&mut self, options: &JsValue) -> Result<(), JsValue> {
+        // String keys accept the same `collation`, `strength` and `numeric`
+        // options as `sort_strings`
+        self.order = StringOrder::from_options(options)?;
+        // Number keys accept `nan` and `signedZero` like the numeric sorts
+        self.numbers = FloatOrder::from_options(options)?;
+        if let Some(kind) = get_string(options, "type")? {
+            set_type(self, &kind)?;
+        }
+        if let Some(nulls) = get_string(options, "nulls")? {
+            self.nulls_first = parse_nulls(&nulls)?;
+        }
+        Ok(())
+    }
+
+    /// Creates an ascending key with missing values last. Without a `type`
+    /// it reads numbers until `infer_kind` has seen the data.
+    pub fn new(path: &str) -> Result<SortKey, JsValue> {
+        if path.is_empty() || path.split('.').any(str::is_empty) {
+            return Err(js_error(&format!("invalid key path '{}'", path)));
+        }
+        Ok(SortKey {
+            path: path.split('.').map(String::from).collect(),
+            descending: false,
+            kind: KeyKind::Number,
+            typed: false,
+            nulls_first: false,
+            order: StringOrder::Binary,
+            numbers: FloatOrder::default(),
+        })
+    }
+
+    /// An identity key configured like an object entry of a specification,
+    /// with `type` defaulting to `kind`.
+    pub fn identity_from_options(
+        kind: KeyKind,
+        descending: bool,
+        options: &JsValue,
+    ) -> Result<SortKey, JsValue> {
+        let mut key = SortKey::identity(kind, descending);
+        key.apply_options(options)?;
+        Ok(key)
+    }
+
+    /// A key that reads each element itself rather than one of its properties.
+    pub fn identity(kind: KeyKind, descending: bool) -> SortKey {
+        SortKey {
+            path: Vec::new(),
+            descending,
+            kind,
+            typed: true,
+            nulls_first: false,
+            order: StringOrder::Binary,
+            numbers: FloatOrder::default(),
+        }
+    }
+
+    /// Follows the property path, returning `undefined` as soon as a
+    /// segment cannot be read.
+    fn lookup(&self, item: &JsValue) -> JsValue {
+        let mut current = item.clone();
+        for segment in &self.path {
+            if !current.is_object() {
+                return JsValue::UNDEFINED;
+            }
+            current =
+                Reflect::get(&current, &JsValue::from_str(segment)).unwrap_or(JsValue::UNDEFINED);
+        }
+        current
+    }
+
+    /// Settles the kind of a key whose specification gave no `type` from
+    /// the first of `items` that has a value for it, read as `mixed`, so
+    /// `"title"` sorts strings rather than finding no numbers. Elements
+    /// before that one have no value of any type, so the kind holds for
+    /// them too. Keys stay numeric while no element has a value.
+    pub fn infer_kind<T>(&mut self, items: &[T], extract: impl Fn(&SortKey, &T) -> KeyValue) {
+        if self.typed {
+            return;
+        }
+        self.kind = KeyKind::Mixed;
+        let inferred = items
+            .iter()
+            .find_map(|item| KeyKind::of(&extract(self, item)));
+        self.kind = inferred.unwrap_or(KeyKind::Number);
+        self.typed = inferred.is_some();
+    }
+
+    pub fn extract(&self, item: &JsValue) -> KeyValue {
+        self.read(&self.lookup(item))
+    }
+
+    /// Reads a value already looked up, `Missing` if it has the wrong type.
+    fn read(&self, value: &JsValue) -> KeyValue {
+        let date = || {
+            value
+                .dyn_ref::<Date>()
+                .map(|date| KeyValue::Date(date.get_time()))
+        };
+        let extracted = match self.kind {
+            KeyKind::Number => value.as_f64().map(KeyValue::Number),
+            KeyKind::String => value.as_string().map(KeyValue::Text),
+            KeyKind::Date => date().or_else(|| value.as_f64().map(KeyValue::Date)),
+            KeyKind::BigInt => BigKey::from_js(value)
+                .or_else(|| value.as_f64().and_then(BigKey::from_integer))
+                .map(KeyValue::BigInt),
+            KeyKind::Boolean => value.as_bool().map(KeyValue::Bool),
+            KeyKind::Mixed => value
+                .as_f64()
+                .map(KeyValue::Number)
+                .or_else(|| value.as_string().map(KeyValue::Text))
+                .or_else(|| value.as_bool().map(KeyValue::Bool))
+                .or_else(|| BigKey::from_js(value).map(KeyValue::BigInt))
+                .or_else(date),
+        };
+        extracted.unwrap_or(KeyValue::Missing)
+    }
+
+    pub fn compare(&self, a: &KeyValue, b: &KeyValue) -> Ordering {
+        let ordering = match (a, b) {
+            (KeyValue::Missing, KeyValue::Missing) => return Ordering::Equal,
+            (KeyValue::Missing, _) if self.nulls_first => return Ordering::Less,
+            (KeyValue::Missing, _) => return Ordering::Greater,
+            (_, KeyValue::Missing) if self.nulls_first => return Ordering::Greater,
+            (_, KeyValue::Missing) => return Ordering::Less,
+            // NaN placement does not depend on the direction either
+            (KeyValue::Number(x), KeyValue::Number(y)) => {
+                return self.numbers.compare(*x, *y, self.descending)
+            }
+            (KeyValue::Date(x), KeyValue::Date(y)) => {
+                return self.numbers.compare(*x, *y, self.descending)
+            }
+            // Numbers and BigInts only meet in mixed keys, where they are
+            // compared by exact value. NaN goes where it goes among numbers.
+            (KeyValue::Number(x), KeyValue::BigInt(_)) if x.is_nan() => {
+                return self.numbers.compare(*x, 0.0, self.descending)
+            }
+            (KeyValue::BigInt(_), KeyValue::Number(y)) if y.is_nan() => {
+                return self.numbers.compare(0.0, *y, self.descending)
+            }
+            (KeyValue::Number(x), KeyValue::BigInt(y)) => self.compare_number_to_big(*x, y),
+            (KeyValue::BigInt(x), KeyValue::Number(y)) => {
+                self.compare_number_to_big(*y, x).reverse()
+            }
+            (KeyValue::Text(x), KeyValue::Text(y)) => self.order.compare(x, y),
+            (KeyValue::BigInt(x), KeyValue::BigInt(y)) => x.cmp(y),
+            (KeyValue::Bool(x), KeyValue::Bool(y)) => x.cmp(y),
+            (x, y) => x.type_rank().cmp(&y.type_rank()),
+        };
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    /// Orders two cells that may be missing the way `compare` orders
+    /// `KeyValue::Missing`, comparing present ones with `present`, which
+    /// applies the direction itself.
+    pub fn compare_cells<T>(
+        &self,
+        a: Option<T>,
+        b: Option<T>,
+        present: impl FnOnce(T, T) -> Ordering,
+    ) -> Ordering {
+        match (a, b) {
+            (Some(x), Some(y)) => present(x, y),
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) if self.nulls_first => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) if self.nulls_first => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+        }
+    }
+
+    /// Compares a number that is not NaN with a BigInt exactly, in
+    /// ascending order. Converting the BigInt to a float would round, making
+    /// `2n ** 53n + 1n` equal to `2 ** 53` and break transitivity. With
+    /// `signedZero`, `0n` sorts like +0.
+    fn compare_number_to_big(&self, x: f64, y: &BigKey) -> Ordering {
+        if x.is_infinite() {
+            return if x > 0.0 {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+        }
+        if x == 0.0 && y.digits.is_empty() {
+            return if self.numbers.signed_zero && x.is_sign_negative() {
+                Ordering::Less
+            } else {
+                Ordering::Equal
+            };
+        }
+        // A fractional number sorts right after its floor
+        let floor = x.floor();
+        let whole = BigKey::from_integer(floor).map_or(Ordering::Equal, |floor| floor.cmp(y));
+        if whole == Ordering::Equal && x != floor {
+            Ordering::Greater
+        } else {
+            whole
+        }
+    }
+}
+
+/// Parses the text form of a specification: comma separated clauses of the
+/// shape `path[:type] [asc|desc] [nulls first|last]`, for example
+/// `"albumId, title:string desc nulls first, id"`. The type is any of
+/// those `set_type` accepts.
+fn parse_clauses(text: &str) -> Result<Vec<SortKey>, JsValue> {
+    text.split(',')
+        .map(|clause| {
+            let mut words = clause.split_whitespace();
+            let target = words
+                .next()
+                .ok_or_else(|| js_error(&format!("empty sort clause in '{}'", text)))?;
+            let (path, kind) = match target.split_once(':') {
+                Some((path, kind)) => (path, Some(kind)),
+                None => (target, None),
+            };
+
+            let mut key = SortKey::new(path)?;
+            if let Some(kind) = kind {
+                set_type(&mut key, kind)?;
+            }
+
+            let mut word = words.next();
+            if let Some(direction @ ("asc" | "desc")) = word {
+                key.descending = parse_direction(direction)?;
+                word = words.next();
+            }
+            if word == Some("nulls") {
+                let placement = words
+                    .next()
+                    .ok_or_else(|| js_error("expected 'first' or 'last' after 'nulls'"))?;
+                key.nulls_first = parse_nulls(placement)?;
+                word = words.next();
+            }
+            if let Some(extra) = word {
+                return Err(js_error(&format!(
+                    "unexpected '{}' in sort clause '{}'",
+                    extra,
+                    clause.trim()
+                )));
+            }
+
+            Ok(key)
+        })
+        .collect()
+}
+
+/// Parses a sort specification: an array of key entries, a single entry, or
+/// the text form accepted by `parse_clauses`.
+pub fn parse_spec(spec: &JsValue) -> Result<Vec<SortKey>, JsValue> {
+    let mut keys = Vec::new();
+    if Array::is_array(spec) {
+        for entry in Array::from(spec).iter() {
+            keys.extend(SortKey::from_js(&entry)?);
+        }
+    } else {
+        keys = SortKey::from_js(spec)?;
+    }
+
+    if keys.is_empty() {
+        return Err(js_error("sort specification must contain at least one key"));
+    }
+    Ok(keys)
+}
+
+/// Extracts every key of a specification from one element.
+pub fn extract_row(keys: &[SortKey], item: &JsValue) -> Vec<KeyValue> {
+    keys.iter().map(|key| key.extract(item)).collect()
+}
+
+/// Compares two rows from `extract_row`, later keys breaking ties of
+/// earlier ones.
+pub fn compare_row_values(keys: &[SortKey], a: &[KeyValue], b: &[KeyValue]) -> Ordering {
+    for ((key, x), y) in keys.iter().zip(a).zip(b) {
+        let ordering = key.compare(x, y);
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Why a probe cannot be used as a bound.
+#[derive(Debug, PartialEq)]
+pub enum ProbeError {
+    /// The probe gives the key at `present` but not the earlier one at
+    /// `missing`.
+    SkippedKey { present: usize, missing: usize },
+    /// The probe's value for the key at this index has the wrong type.
+    WrongType(usize),
+}
+
+/// The bound a probe stands for, from the value it gives for each key, or
+/// `None` where it has no such property: the values of its leading keys, up
+/// to the first it does not have. A probe must give a prefix of the keys,
+/// since skipping one would leave rows in an order the bound cannot be
+/// searched by. A value of the wrong type is an error too, rather than
+/// being read as missing and silently shortening the bound.
+pub fn bound_from_probe(fields: Vec<Option<KeyValue>>) -> Result<Vec<KeyValue>, ProbeError> {
+    if let Some(index) = fields
+        .iter()
+        .position(|field| matches!(field, Some(KeyValue::Missing)))
+    {
+        return Err(ProbeError::WrongType(index));
+    }
+    let prefix = fields
+        .iter()
+        .position(Option::is_none)
+        .unwrap_or(fields.len());
+    if let Some(offset) = fields[prefix..].iter().position(Option::is_some) {
+        return Err(ProbeError::SkippedKey {
+            present: prefix + offset,
+            missing: prefix,
+        });
+    }
+    Ok(fields.into_iter().map_while(|field| field).collect())
+}
+
+/// Extracts the bound a probe object stands for, see `bound_from_probe`.
+/// Properties that are `undefined` or `null` count as absent.
+pub fn extract_bound(keys: &[SortKey], probe: &JsValue) -> Result<Vec<KeyValue>, JsValue> {
+    let fields = keys
+        .iter()
+        .map(|key| {
+            let value = key.lookup(probe);
+            (!value.is_undefined() && !value.is_null()).then(|| key.read(&value))
+        })
+        .collect();
+    bound_from_probe(fields).map_err(|err| match err {
+        ProbeError::SkippedKey { present, missing } => js_error(&format!(
+            "probe has key '{}' but not the earlier key '{}'",
+            keys[present].path.join("."),
+            keys[missing].path.join(".")
+        )),
+        ProbeError::WrongType(index) => js_type_error(&format!(
+            "probe value for key '{}' cannot be read as type '{}'",
+            keys[index].path.join("."),
+            keys[index].kind.name()
+        )),
+    })
+}
+
+/// Compares a row against a bound from `extract_bound`. Keys after the
+/// bound's prefix match every value, so `{ albumId: 3 }` compares equal to
+/// all rows of album 3 whatever their other keys are.
+pub fn compare_to_bound(keys: &[SortKey], row: &[KeyValue], bound: &[KeyValue]) -> Ordering {
+    compare_row_values(keys, row, bound)
+}
+
+/// Keys extracted once per element, stored column by column.
+pub struct KeyTable {
+    keys: Vec<SortKey>,
+    columns: Vec<Vec<KeyValue>>,
+    len: usize,
+}
+
+impl KeyTable {
+    pub fn build(keys: Vec<SortKey>, items: &[JsValue]) -> KeyTable {
+        KeyTable::build_with(keys, items, SortKey::extract)
+    }
+
+    /// Like `build`, for elements that are not JS values.
+    pub fn build_with<T>(
+        mut keys: Vec<SortKey>,
+        items: &[T],
+        extract: impl Fn(&SortKey, &T) -> KeyValue,
+    ) -> KeyTable {
+        for key in &mut keys {
+            key.infer_kind(items, &extract);
+        }
+        let columns = keys
+            .iter()
+            .map(|key| items.iter().map(|item| extract(key, item)).collect())
+            .collect();
+        KeyTable {
+            keys,
+            columns,
+            len: items.len(),
+        }
+    }
+
+    pub fn compare_rows(&self, a: usize, b: usize) -> Ordering {
+        for (key, column) in self.keys.iter().zip(&self.columns) {
+            let ordering = key.compare(&column[a], &column[b]);
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+
+    /// Like `compare_rows`, with ties broken by input position, which gives
+    /// unstable algorithms the same result as a stable sort.
+    pub fn compare_rows_stable(&self, a: usize, b: usize) -> Ordering {
+        self.compare_rows(a, b).then(a.cmp(&b))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the row indices in sorted order. Ties keep their input order
+    /// when the sort is `stable`.
+    pub fn sorted_indices(
+        &self,
+        algorithm: SortAlgorithm,
+        stable: bool,
+        stats: &mut SortStats,
+    ) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.len).collect();
+        algorithms::sort_by(&mut indices, algorithm, stable, stats, |&a, &b| {
+            self.compare_rows(a, b)
+        });
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(text: &str) -> KeyValue {
+        KeyValue::BigInt(BigKey::parse(text))
+    }
+
+    #[test]
+    fn numbers_and_bigints_compare_exactly() {
+        let key = SortKey::identity(KeyKind::Mixed, false);
+        let two_53 = KeyValue::Number(9_007_199_254_740_992.0);
+        // 2n ** 53n < 2 ** 53 == 2n ** 53n < 2n ** 53n + 1n
+        assert_eq!(
+            key.compare(&big("9007199254740992"), &two_53),
+            Ordering::Equal
+        );
+        assert_eq!(
+            key.compare(&two_53, &big("9007199254740993")),
+            Ordering::Less
+        );
+        assert_eq!(
+            key.compare(&big("9007199254740993"), &two_53),
+            Ordering::Greater
+        );
+
+        let cases = [
+            (2.5, "2", Ordering::Greater),
+            (2.5, "3", Ordering::Less),
+            (-2.5, "-2", Ordering::Less),
+            (-2.5, "-3", Ordering::Greater),
+            (-0.0, "0", Ordering::Equal),
+            (1e300, "1", Ordering::Greater),
+            (f64::INFINITY, "99999999999999999999999", Ordering::Greater),
+            (
+                f64::NEG_INFINITY,
+                "-99999999999999999999999",
+                Ordering::Less,
+            ),
+        ];
+        for (number, bigint, expected) in cases {
+            let number = KeyValue::Number(number);
+            assert_eq!(key.compare(&number, &big(bigint)), expected);
+            assert_eq!(key.compare(&big(bigint), &number), expected.reverse());
+        }
+    }
+
+    #[test]
+    fn nan_and_signed_zero_against_bigints() {
+        let mut key = SortKey::identity(KeyKind::Mixed, true);
+        let nan = KeyValue::Number(f64::NAN);
+        // NaN stays last whatever the direction
+        assert_eq!(key.compare(&nan, &big("5")), Ordering::Greater);
+        assert_eq!(key.compare(&big("5"), &nan), Ordering::Less);
+
+        key.descending = false;
+        key.numbers.signed_zero = true;
+        let negative_zero = KeyValue::Number(-0.0);
+        assert_eq!(key.compare(&negative_zero, &big("0")), Ordering::Less);
+        assert_eq!(
+            key.compare(&KeyValue::Number(0.0), &big("0")),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn prefix_bounds_partition_sorted_rows() {
+        let keys = [
+            SortKey::identity(KeyKind::Number, false),
+            SortKey::identity(KeyKind::Number, false),
+        ];
+        let rows: Vec<Vec<KeyValue>> = [(1.0, 9.0), (2.0, 1.0), (2.0, 5.0), (3.0, 0.0)]
+            .iter()
+            .map(|&(a, b)| vec![KeyValue::Number(a), KeyValue::Number(b)])
+            .collect();
+        let orderings = |bound: &[KeyValue]| -> Vec<Ordering> {
+            rows.iter()
+                .map(|row| compare_to_bound(&keys, row, bound))
+                .collect()
+        };
+        use Ordering::*;
+        assert_eq!(
+            orderings(&[KeyValue::Number(2.0)]),
+            [Less, Equal, Equal, Greater]
+        );
+        assert_eq!(
+            orderings(&[KeyValue::Number(2.0), KeyValue::Number(5.0)]),
+            [Less, Less, Equal, Greater]
+        );
+        assert_eq!(orderings(&[]), [Equal; 4]);
+    }
+
+    #[test]
+    fn big_keys_compare_by_value() {
+        let sorted = [
+            "-100000000000000000000",
+            "-99999999999999999999",
+            "-10",
+            "-9",
+            "0",
+            "7",
+            "10",
+            "18446744073709551616",
+        ];
+        for (i, a) in sorted.iter().enumerate() {
+            for (j, b) in sorted.iter().enumerate() {
+                assert_eq!(BigKey::parse(a).cmp(&BigKey::parse(b)), i.cmp(&j));
+            }
+        }
+    }
+
+    #[test]
+    fn big_keys_ignore_leading_zeros_and_the_sign_of_zero() {
+        assert!(BigKey::parse("007") == BigKey::parse("7"));
+        assert!(BigKey::parse("-007") == BigKey::parse("-7"));
+        assert!(BigKey::parse("-0") == BigKey::parse("0"));
+        assert!(BigKey::parse("-000") == BigKey::parse(""));
+        assert!(BigKey::parse("-1") < BigKey::parse("-0"));
+    }
+
+    #[test]
+    fn big_keys_convert_integral_numbers_only() {
+        assert!(BigKey::from_integer(-0.0) == Some(BigKey::parse("0")));
+        assert!(BigKey::from_integer(1e21) == Some(BigKey::parse("1000000000000000000000")));
+        assert!(
+            BigKey::from_integer(-9_007_199_254_740_992.0)
+                == Some(BigKey::parse("-9007199254740992"))
+        );
+        assert!(BigKey::from_integer(0.5).is_none());
+        assert!(BigKey::from_integer(f64::NAN).is_none());
+        assert!(BigKey::from_integer(f64::INFINITY).is_none());
+    }
+}
@@ -0,0 +1,273 @@
+use wasm_bindgen::prelude::*;
+
+/// Operators and punctuation, longest first so that `===` is not read as
+/// `==` followed by `=`.
+const PUNCTUATION: &[&str] = &[
+    "===", "!==", "==", "!=", "<=", ">=", "&&", "||", "<", ">", "+", "-", "*", "/", "%", "!", "(",
+    ")", ".", ",", "?", ":",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Punct(&'static str),
+    End,
+}
+
+/// A token and the byte range it was read from.
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// An error in an expression, located by byte offset.
+#[derive(Debug)]
+pub struct SyntaxError {
+    pub message: String,
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl SyntaxError {
+    pub fn new(message: impl Into<String>, offset: usize, len: usize) -> SyntaxError {
+        SyntaxError {
+            message: message.into(),
+            offset,
+            len,
+        }
+    }
+
+    /// Converts to a JS `SyntaxError` carrying `expression`, `position` and
+    /// `length` properties. Positions count UTF-16 code units, like JS
+    /// string indices.
+    pub fn to_js(&self, source: &str) -> JsValue {
+        let start = self.offset.min(source.len());
+        let end = (self.offset + self.len).min(source.len());
+        let position = source[..start].encode_utf16().count();
+        let length = source[start..end].encode_utf16().count();
+
+        let error = js_sys::SyntaxError::new(&format!(
+            "{} at position {} in '{}'",
+            self.message, position, source
+        ));
+        for (name, value) in [
+            ("expression", JsValue::from_str(source)),
+            ("position", JsValue::from(position as u32)),
+            ("length", JsValue::from(length as u32)),
+        ] {
+            // Setting a property on a fresh error object cannot fail
+            let _ = js_sys::Reflect::set(&error, &JsValue::from_str(name), &value);
+        }
+        error.into()
+    }
+}
+
+/// Splits an expression into tokens, ending with a `TokenKind::End` token.
+pub fn tokenize(source: &str) -> Result<Vec<Token>, SyntaxError> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let c = bytes[offset];
+        let start = offset;
+
+        if c.is_ascii_whitespace() {
+            offset += 1;
+            continue;
+        }
+
+        let kind = if c.is_ascii_digit()
+            || (c == b'.' && bytes.get(offset + 1).is_some_and(u8::is_ascii_digit))
+        {
+            offset = number_end(bytes, offset);
+            let text = &source[start..offset];
+            let value = text.parse().map_err(|_| {
+                SyntaxError::new(format!("invalid number '{}'", text), start, offset - start)
+            })?;
+            TokenKind::Number(value)
+        } else if c == b'"' || c == b'\'' {
+            let (text, end) = string_literal(source, offset)?;
+            offset = end;
+            TokenKind::Str(text)
+        } else if c.is_ascii_alphabetic() || c == b'_' || c == b'$' {
+            while offset < bytes.len()
+                && (bytes[offset].is_ascii_alphanumeric()
+                    || bytes[offset] == b'_'
+                    || bytes[offset] == b'$')
+            {
+                offset += 1;
+            }
+            TokenKind::Ident(source[start..offset].to_string())
+        } else if let Some(punct) = PUNCTUATION
+            .iter()
+            .find(|punct| source[offset..].starts_with(**punct))
+        {
+            offset += punct.len();
+            TokenKind::Punct(punct)
+        } else {
+            let len = source[offset..].chars().next().map_or(1, char::len_utf8);
+            return Err(SyntaxError::new(
+                format!("unexpected character '{}'", &source[offset..offset + len]),
+                offset,
+                len,
+            ));
+        };
+
+        tokens.push(Token {
+            kind,
+            offset: start,
+            len: offset - start,
+        });
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::End,
+        offset: source.len(),
+        len: 0,
+    });
+    Ok(tokens)
+}
+
+/// The end of a decimal literal such as `12`, `0.5`, `.5` or `1e-3`.
+fn number_end(bytes: &[u8], mut offset: usize) -> usize {
+    let digits = |offset: &mut usize| {
+        while *offset < bytes.len() && bytes[*offset].is_ascii_digit() {
+            *offset += 1;
+        }
+    };
+
+    digits(&mut offset);
+    if bytes.get(offset) == Some(&b'.') {
+        offset += 1;
+        digits(&mut offset);
+    }
+    if matches!(bytes.get(offset), Some(b'e' | b'E')) {
+        let mut exponent = offset + 1;
+        if matches!(bytes.get(exponent), Some(b'+' | b'-')) {
+            exponent += 1;
+        }
+        if bytes.get(exponent).is_some_and(u8::is_ascii_digit) {
+            offset = exponent;
+            digits(&mut offset);
+        }
+    }
+    offset
+}
+
+/// Reads a single or double quoted string starting at `start`, returning
+/// its value and the offset just past the closing quote.
+fn string_literal(source: &str, start: usize) -> Result<(String, usize), SyntaxError> {
+    let mut chars = source[start..].char_indices();
+    let (_, quote) = chars.next().unwrap_or((0, '"'));
+    let mut text = String::new();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, 'n')) => text.push('\n'),
+                Some((_, 't')) => text.push('\t'),
+                Some((_, escaped)) => text.push(escaped),
+                None => break,
+            },
+            c if c == quote => return Ok((text, start + index + 1)),
+            c => text.push(c),
+        }
+    }
+
+    Err(SyntaxError::new(
+        "unterminated string",
+        start,
+        source.len() - start,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        tokenize(source)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    #[test]
+    fn reads_every_token_kind() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("a.id >= 1.5e3 && $b_2 !== 'x'"),
+            [
+                Ident("a".into()),
+                Punct("."),
+                Ident("id".into()),
+                Punct(">="),
+                Number(1500.0),
+                Punct("&&"),
+                Ident("$b_2".into()),
+                Punct("!=="),
+                Str("x".into()),
+                End,
+            ]
+        );
+        assert_eq!(kinds(""), [End]);
+        assert_eq!(kinds(".5+5."), [Number(0.5), Punct("+"), Number(5.0), End]);
+        // An exponent without digits is not part of the number
+        assert_eq!(kinds("1e"), [Number(1.0), Ident("e".into()), End]);
+    }
+
+    #[test]
+    fn punctuation_takes_the_longest_match() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("a===b!=c<=d"),
+            [
+                Ident("a".into()),
+                Punct("==="),
+                Ident("b".into()),
+                Punct("!="),
+                Ident("c".into()),
+                Punct("<="),
+                Ident("d".into()),
+                End,
+            ]
+        );
+    }
+
+    #[test]
+    fn strings_unescape() {
+        assert_eq!(
+            kinds(r#""a\"b\n" 'c\'\t'"#),
+            [
+                TokenKind::Str("a\"b\n".into()),
+                TokenKind::Str("c'\t".into()),
+                TokenKind::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_record_their_byte_ranges() {
+        let tokens = tokenize("é + 'ü'").unwrap_err();
+        assert_eq!((tokens.offset, tokens.len), (0, 2));
+
+        let tokens = tokenize("x  'ü'").unwrap();
+        assert_eq!((tokens[1].offset, tokens[1].len), (3, 4));
+        assert_eq!((tokens[2].offset, tokens[2].len), (7, 0));
+    }
+
+    #[test]
+    fn reports_unterminated_strings() {
+        let error = tokenize("a == 'abc").unwrap_err();
+        assert_eq!(error.message, "unterminated string");
+        assert_eq!((error.offset, error.len), (5, 4));
+        assert!(tokenize("'abc\\").is_err());
+        assert!(tokenize("a # b").is_err());
+    }
+}
@@ -1,10 +1,44 @@
-use js_sys::{Array, Function};
+use js_sys::{Array, Function, Object, Reflect, Uint32Array};
 use wasm_bindgen::prelude::*;
 
+mod algorithms;
+mod collation;
+mod collection;
+mod expr;
+mod json;
+mod keys;
+mod lexer;
+mod numeric;
+mod options;
+mod page;
+mod photo;
+mod queue;
+mod radix;
+mod rng;
+mod sample;
+mod select;
+mod table;
+mod validate;
+
+pub use collection::SortedCollection;
+pub use queue::PriorityQueue;
+pub use sample::Reservoir;
+pub use table::Table;
+
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     fn log(s: &str);
+
+    // The global `Number()` conversion, used to coerce comparator results
+    // and sort inputs
+    #[wasm_bindgen(js_name = Number, catch)]
+    fn to_number(value: &JsValue) -> Result<f64, JsValue>;
+
+    // The global `String()` conversion, used for the default sort order and
+    // to coerce sort inputs
+    #[wasm_bindgen(js_name = String, catch)]
+    fn to_string(value: &JsValue) -> Result<js_sys::JsString, JsValue>;
 }
 
 #[wasm_bindgen]
@@ -17,43 +51,165 @@ macro_rules! console_log {
     ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
 }
 
+pub(crate) fn js_error(message: &str) -> JsValue {
+    js_sys::Error::new(message).into()
+}
+
+pub(crate) fn js_type_error(message: &str) -> JsValue {
+    js_sys::TypeError::new(message).into()
+}
+
+/// Calls a comparator and turns its result into an `Ordering`.
+///
+/// Like `Array.prototype.sort`, the result is converted with `ToNumber` and
+/// NaN counts as equal. `Number()` would also accept a BigInt, which
+/// `ToNumber` rejects with a TypeError, so BigInt results are rejected
+/// first. In strict mode anything other than a non-NaN number is an error
+/// instead.
+fn call_predicate(
+    predicate: &Function,
+    a: &JsValue,
+    b: &JsValue,
+    strict: bool,
+) -> Result<std::cmp::Ordering, JsValue> {
+    let result = predicate.call2(&JsValue::null(), a, b)?;
+    let num = if strict {
+        match result.as_f64() {
+            Some(num) if !num.is_nan() => num,
+            _ => {
+                return Err(js_error(&format!(
+                    "comparator returned {:?} instead of a number",
+                    result
+                )))
+            }
+        }
+    } else if result.is_bigint() {
+        return Err(js_type_error(
+            "cannot convert a BigInt comparator result to a number",
+        ));
+    } else {
+        to_number(&result)?
+    };
+
+    Ok(if num < 0.0 {
+        std::cmp::Ordering::Less
+    } else if num > 0.0 {
+        std::cmp::Ordering::Greater
+    } else {
+        std::cmp::Ordering::Equal
+    })
+}
+
+/// Converts a value to its string form as UTF-16 code units, following the
+/// `ToString` step of `Array.prototype.sort`.
+fn utf16_string(value: &JsValue) -> Result<Vec<u16>, JsValue> {
+    if value.is_symbol() {
+        return Err(js_error("cannot convert a Symbol value to a string"));
+    }
+    let string = to_string(value)?;
+    // Strings with lone surrogates would be mangled by a Rust `String`
+    if string.is_valid_utf16() {
+        Ok(String::from(string).encode_utf16().collect())
+    } else {
+        Ok(string.iter().collect())
+    }
+}
+
+/// Sorts an array like `Array.prototype.sort`.
+///
+/// With a `predicate`, the first exception it throws aborts the sort and is
+/// returned as the error, and `options.strict` rejects comparator results
+/// that are not numbers (or are NaN) instead of coercing them. Without one,
+/// elements are compared by their string forms, code unit by code unit.
+/// Either way `undefined` elements are moved to the end without being
+/// compared, and equal elements keep their relative order.
+///
+/// `options.algorithm` selects the sorting algorithm, and `options.stable:
+/// false` lets the standard library sort reorder equal elements. With a
+/// predicate, `"auto"` and `"comparison"` run timsort instead, since the
+/// standard library sort may panic on a comparator that is inconsistent or
+/// fails partway through. Passing an object as `options.stats` fills it
+/// with `{ algorithm, comparisons, moves, callbacks, stable, cached }`
+/// counters. Every full sort takes these options; the numeric sorts default
+/// to unstable, since equal numbers can only be told apart when they are -0
+/// and +0. The partial orderings `top_k`, `partial_sort`, `select_nth` and
+/// `top_k_f64` always run their own selection algorithm and take none of
+/// them.
 #[wasm_bindgen]
-pub fn sort_array(array: &Array, predicate: &Function) -> Result<Array, JsValue> {
+pub fn sort_array(
+    array: &Array,
+    predicate: Option<Function>,
+    options: &JsValue,
+) -> Result<Array, JsValue> {
     console_log!("Starting sort with array length: {}", array.length());
 
+    let strict = options::get_bool(options, "strict")?.unwrap_or(false);
     let mut items: Vec<(JsValue, usize)> = Vec::new();
+    let mut undefined_count = 0;
 
     // Convert JS array to Rust vector with original indices
     for i in 0..array.length() {
         let item = array.get(i);
-        items.push((item, i as usize));
-    }
-
-    // Sort using the predicate function
-    items.sort_by(|a, b| {
-        let result = predicate.call2(&JsValue::null(), &a.0, &b.0);
-        match result {
-            Ok(val) => {
-                if let Some(num) = val.as_f64() {
-                    if num < 0.0 {
-                        std::cmp::Ordering::Less
-                    } else if num > 0.0 {
-                        std::cmp::Ordering::Greater
-                    } else {
+        if item.is_undefined() {
+            undefined_count += 1;
+        } else {
+            items.push((item, i as usize));
+        }
+    }
+
+    let algorithm = algorithms::SortAlgorithm::comparison_from_options(options)?;
+    let stable = algorithms::stable_from_options(options, algorithm, true)?;
+    let mut stats = algorithms::SortStats::new();
+    // Positions into `items`, so the algorithms never clone a JsValue
+    let mut order: Vec<usize> = (0..items.len()).collect();
+
+    match predicate {
+        Some(predicate) => {
+            // Sort using the predicate function. The sort cannot be
+            // interrupted, so once the comparator has failed every remaining
+            // comparison is skipped.
+            let mut failure: Option<JsValue> = None;
+            let mut callbacks = 0;
+            algorithms::sort_by_untrusted(&mut order, algorithm, stable, &mut stats, |&a, &b| {
+                if failure.is_some() {
+                    return std::cmp::Ordering::Equal;
+                }
+                callbacks += 1;
+                match call_predicate(&predicate, &items[a].0, &items[b].0, strict) {
+                    Ok(ordering) => ordering,
+                    Err(err) => {
+                        failure = Some(err);
                         std::cmp::Ordering::Equal
                     }
-                } else {
-                    std::cmp::Ordering::Equal
                 }
+            });
+            stats.callbacks = callbacks;
+
+            if let Some(err) = failure {
+                console_log!("Sort aborted by comparator error");
+                return Err(err);
             }
-            Err(_) => std::cmp::Ordering::Equal,
         }
-    });
+        None => {
+            // Convert every element to a string once, then compare natively
+            let strings = items
+                .iter()
+                .map(|(item, _)| utf16_string(item))
+                .collect::<Result<Vec<_>, _>>()?;
+            algorithms::sort_by(&mut order, algorithm, stable, &mut stats, |&a, &b| {
+                strings[a].cmp(&strings[b])
+            });
+        }
+    }
+    stats.report(options)?;
 
     // Convert back to JS array
     let result_array = Array::new();
-    for (item, _) in items {
-        result_array.push(&item);
+    for index in order {
+        result_array.push(&items[index].0);
+    }
+    for _ in 0..undefined_count {
+        result_array.push(&JsValue::UNDEFINED);
     }
 
     console_log!(
@@ -63,25 +219,304 @@ pub fn sort_array(array: &Array, predicate: &Function) -> Result<Array, JsValue>
     Ok(result_array)
 }
 
+/// Checks a comparator for the properties a sort relies on, using elements
+/// of `sample`: `compare(a, a)` is 0 (reflexivity), swapping the arguments
+/// flips the sign (antisymmetry), and the order is transitive. Results that
+/// are not numbers, such as the booleans from `(a, b) => a > b`, and
+/// exceptions are reported too.
+///
+/// `options.pairs` and `options.triples` (1000 each by default) bound how
+/// many pairs and triples are checked; smaller samples are checked
+/// exhaustively, larger ones at random from `options.seed`. Returns
+/// `{ valid, seed, checkedPairs, checkedTriples, counts, violations }`,
+/// listing up to `options.maxViolations` (default 20) violations.
+#[wasm_bindgen]
+pub fn validate_comparator(
+    sample: &Array,
+    predicate: &Function,
+    options: &JsValue,
+) -> Result<Object, JsValue> {
+    console_log!("Validating comparator on {} elements", sample.length());
+    let items: Vec<JsValue> = sample.iter().collect();
+    validate::Checker::new(&items, predicate).run(options)
+}
+
+/// Sorts with a comparator written as an expression instead of a JS
+/// function, e.g. `a.albumId - b.albumId || b.title.length - a.title.length`.
+///
+/// The expression is parsed once and every property it reads is extracted
+/// once per element, so the sort runs without calling back into JS. Results
+/// are read like `sort_array` reads a predicate's, with NaN counting as
+/// equal, and `undefined` elements go to the end. As in `sort_array`,
+/// `"auto"` and `"comparison"` run timsort. `localeCompare` uses the
+/// collation options of `sort_strings`. A malformed expression is reported
+/// as a `SyntaxError` with `expression`, `position` and `length` properties.
+#[wasm_bindgen]
+pub fn sort_by_expression(
+    array: &Array,
+    expression: &str,
+    options: &JsValue,
+) -> Result<Array, JsValue> {
+    console_log!(
+        "Sorting by expression with array length: {}",
+        array.length()
+    );
+
+    let program =
+        expr::Program::parse(expression, &["a", "b"]).map_err(|err| err.to_js(expression))?;
+    let order = collation::StringOrder::from_options(options)?;
+    let algorithm = algorithms::SortAlgorithm::comparison_from_options(options)?;
+    let stable = algorithms::stable_from_options(options, algorithm, true)?;
+    let mut stats = algorithms::SortStats::new();
+
+    let mut items = Vec::new();
+    let mut undefined_count = 0;
+    for item in array.iter() {
+        if item.is_undefined() {
+            undefined_count += 1;
+        } else {
+            items.push(item);
+        }
+    }
+
+    let rows: Vec<_> = items.iter().map(|item| program.extract(item)).collect();
+    let mut indices: Vec<usize> = (0..items.len()).collect();
+    // NaN results count as equal, so the order need not be consistent
+    algorithms::sort_by_untrusted(&mut indices, algorithm, stable, &mut stats, |&a, &b| {
+        let result = program.eval(&[&rows[a], &rows[b]], &order).to_number();
+        result
+            .partial_cmp(&0.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    stats.report(options)?;
+
+    let result_array = Array::new();
+    for index in indices {
+        result_array.push(&items[index]);
+    }
+    for _ in 0..undefined_count {
+        result_array.push(&JsValue::UNDEFINED);
+    }
+    Ok(result_array)
+}
+
+/// Evaluates a filter expression against every element, returning the
+/// indices of those it is truthy for.
+fn filter_matches(array: &Array, expression: &str, options: &JsValue) -> Result<Vec<u32>, JsValue> {
+    let program = expr::Program::parse_record(expression).map_err(|err| err.to_js(expression))?;
+    let order = collation::StringOrder::from_options(options)?;
+    let mut matches = Vec::new();
+    for (index, item) in array.iter().enumerate() {
+        let row = program.extract(&item);
+        if program.eval(&[&row], &order).truthy() {
+            matches.push(index as u32);
+        }
+    }
+    Ok(matches)
+}
+
+/// Returns the elements of `array` for which a filter expression holds,
+/// e.g. `albumId > 5 && title contains "quo" && id % 2 == 0`.
+///
+/// The expression uses the language of `sort_by_expression` with the
+/// element's properties named directly, plus the `contains`, `startsWith`
+/// and `endsWith` string operators. It is parsed once and evaluated in Rust
+/// for every element, without calling back into JS. A malformed expression,
+/// or one nested more than 256 levels deep, is reported as a `SyntaxError`
+/// like in `sort_by_expression`.
+#[wasm_bindgen]
+pub fn filter_by_expression(
+    array: &Array,
+    expression: &str,
+    options: &JsValue,
+) -> Result<Array, JsValue> {
+    console_log!(
+        "Filtering by expression with array length: {}",
+        array.length()
+    );
+
+    let result_array = Array::new();
+    for index in filter_matches(array, expression, options)? {
+        result_array.push(&array.get(index));
+    }
+    Ok(result_array)
+}
+
+/// Like `filter_by_expression`, but returns the indices of the matching
+/// elements, in increasing order.
+#[wasm_bindgen]
+pub fn filter_indices_by_expression(
+    array: &Array,
+    expression: &str,
+    options: &JsValue,
+) -> Result<Uint32Array, JsValue> {
+    Ok(Uint32Array::from(
+        &filter_matches(array, expression, options)?[..],
+    ))
+}
+
+/// Sorts objects by a list of property paths without calling back into JS.
+///
+/// `spec` is an array of `{ path, direction, type }` entries, e.g.
+/// `[{ path: "albumId", type: "number" }, { path: "title", type: "string", direction: "desc" }]`.
+/// Keys are extracted once per element; later keys break ties of earlier ones.
+/// A key without a `type` takes the type of the first value it finds, so
+/// `"title"` sorts titles as strings.
+#[wasm_bindgen]
+pub fn sort_by_keys(array: &Array, spec: &JsValue, options: &JsValue) -> Result<Array, JsValue> {
+    console_log!("Sorting by keys with array length: {}", array.length());
+
+    let keys = keys::parse_spec(spec)?;
+    let algorithm = algorithms::SortAlgorithm::comparison_from_options(options)?;
+    let stable = algorithms::stable_from_options(options, algorithm, true)?;
+    let mut stats = algorithms::SortStats::new();
+    let items: Vec<JsValue> = array.iter().collect();
+    let table = keys::KeyTable::build(keys, &items);
+    let indices = table.sorted_indices(algorithm, stable, &mut stats);
+    stats.report(options)?;
+
+    let result_array = Array::new();
+    for index in indices {
+        result_array.push(&items[index]);
+    }
+
+    Ok(result_array)
+}
+
+/// Sorts a JSON array of objects by `spec`, taking and returning JSON text.
+///
+/// The same specification as `sort_by_keys` applies, but the elements never
+/// become JS objects: parsing, key extraction, sorting and serialization all
+/// happen in Rust. Property order inside each element is preserved.
+#[wasm_bindgen]
+pub fn sort_json(json: &str, spec: &JsValue, options: &JsValue) -> Result<String, JsValue> {
+    console_log!("Sorting JSON text of length: {}", json.len());
+
+    let keys = keys::parse_spec(spec)?;
+    let algorithm = algorithms::SortAlgorithm::comparison_from_options(options)?;
+    let stable = algorithms::stable_from_options(options, algorithm, true)?;
+    let mut stats = algorithms::SortStats::new();
+    let sorted = json::sort(json, keys, algorithm, stable, &mut stats)?;
+    stats.report(options)?;
+
+    Ok(sorted)
+}
+
+/// Sorts an array of photo records by `field`: `"id"`, `"albumId"`,
+/// `"title"` or `"titleLength"`.
+///
+/// The records are deserialized into typed Rust structs in one pass and
+/// sorted without calling back into JS, which makes this the predicate-free
+/// counterpart of `sort_array` for PhotoSorter's dataset. The result holds
+/// new objects with just the `Photo` fields. Titles accept the same
+/// `collation` options as `sort_strings`.
+#[wasm_bindgen]
+pub fn sort_photos(
+    photos: JsValue,
+    field: &str,
+    ascending: bool,
+    options: &JsValue,
+) -> Result<JsValue, JsValue> {
+    let field = photo::PhotoField::parse(field)?;
+    let records: Vec<photo::Photo> = serde_wasm_bindgen::from_value(photos)?;
+    console_log!("Sorting photos with array length: {}", records.len());
+
+    let order = collation::StringOrder::from_options(options)?;
+    let algorithm = algorithms::SortAlgorithm::comparison_from_options(options)?;
+    let stable = algorithms::stable_from_options(options, algorithm, true)?;
+    let mut stats = algorithms::SortStats::new();
+    let sorted = photo::sort(
+        records, field, !ascending, &order, algorithm, stable, &mut stats,
+    );
+    stats.report(options)?;
+
+    Ok(serde_wasm_bindgen::to_value(&sorted)?)
+}
+
+/// Checks that `sorted` is exactly what a stable sort of `original` by
+/// `spec` gives: the same elements, in key order, with elements whose keys
+/// are equal in their original relative order. Elements are matched by
+/// identity, so this is meaningful for arrays of objects.
+#[wasm_bindgen]
+pub fn is_stable_result(original: &Array, sorted: &Array, spec: &JsValue) -> Result<bool, JsValue> {
+    if original.length() != sorted.length() {
+        return Ok(false);
+    }
+
+    let items: Vec<JsValue> = original.iter().collect();
+    let mut stats = algorithms::SortStats::new();
+    let expected = keys::KeyTable::build(keys::parse_spec(spec)?, &items).sorted_indices(
+        algorithms::SortAlgorithm::Auto,
+        true,
+        &mut stats,
+    );
+
+    Ok(expected
+        .iter()
+        .zip(sorted.iter())
+        .all(|(&index, item)| Object::is(&items[index], &item)))
+}
+
+/// Builds the `{ values, rejectedCount, rejected }` object returned by
+/// `sort_numbers` and `sort_strings`, where `rejected` holds the original
+/// indices of the elements that were left out.
+fn sort_report(values: &Array, rejected: &[u32]) -> Result<Object, JsValue> {
+    let report = Object::new();
+    Reflect::set(&report, &JsValue::from_str("values"), values)?;
+    Reflect::set(
+        &report,
+        &JsValue::from_str("rejectedCount"),
+        &JsValue::from(rejected.len() as u32),
+    )?;
+    Reflect::set(
+        &report,
+        &JsValue::from_str("rejected"),
+        &Uint32Array::from(rejected),
+    )?;
+    Ok(report)
+}
+
+/// Sorts numbers. Elements that are not numbers are reported as rejected,
+/// unless `options.coerce` converts them with `Number()` first.
+///
+/// NaNs go last unless `options.nan` is `"first"`, and `options.signedZero`
+/// sorts -0 before +0. The same options apply to every numeric sort.
+///
+/// With the default `options.algorithm` of `"auto"`, large inputs are
+/// sorted with radix sort.
 #[wasm_bindgen]
-pub fn sort_numbers(numbers: &Array, ascending: bool) -> Array {
+pub fn sort_numbers(
+    numbers: &Array,
+    ascending: bool,
+    options: &JsValue,
+) -> Result<Object, JsValue> {
     console_log!("Sorting numbers, ascending: {}", ascending);
 
+    let order = numeric::FloatOrder::from_options(options)?;
+    let algorithm = algorithms::SortAlgorithm::from_options(options)?;
+    let stable = algorithms::stable_from_options(options, algorithm, false)?;
+    let mut stats = algorithms::SortStats::new();
+    let coerce = options::get_bool(options, "coerce")?.unwrap_or(false);
     let mut nums: Vec<f64> = Vec::new();
+    let mut rejected: Vec<u32> = Vec::new();
 
     // Convert JS array to Rust vector
     for i in 0..numbers.length() {
-        if let Some(num) = numbers.get(i).as_f64() {
-            nums.push(num);
+        let value = numbers.get(i);
+        let num = if coerce {
+            to_number(&value).ok()
+        } else {
+            value.as_f64()
+        };
+        match num {
+            Some(num) => nums.push(num),
+            None => rejected.push(i),
         }
     }
 
     // Sort
-    if ascending {
-        nums.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
-    } else {
-        nums.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
-    }
+    numeric::sort_floats(&mut nums, ascending, order, algorithm, stable, &mut stats);
+    stats.report(options)?;
 
     // Convert back to JS array
     let result = Array::new();
@@ -89,36 +524,371 @@ pub fn sort_numbers(numbers: &Array, ascending: bool) -> Array {
         result.push(&JsValue::from_f64(num));
     }
 
-    result
+    sort_report(&result, &rejected)
+}
+
+// In-place sorting of typed arrays. wasm-bindgen copies the typed array
+// into linear memory once and writes it back after the call, so no element
+// is boxed as a JsValue. They take the same `algorithm` and `stats` options
+// as `sort_numbers`, and float sorts its NaN and signed zero options too.
+
+#[wasm_bindgen]
+pub fn sort_f64(values: &mut [f64], ascending: bool, options: &JsValue) -> Result<(), JsValue> {
+    console_log!(
+        "Sorting {} f64 values, ascending: {}",
+        values.len(),
+        ascending
+    );
+    let order = numeric::FloatOrder::from_options(options)?;
+    let algorithm = algorithms::SortAlgorithm::from_options(options)?;
+    let stable = algorithms::stable_from_options(options, algorithm, false)?;
+    let mut stats = algorithms::SortStats::new();
+    numeric::sort_floats(values, ascending, order, algorithm, stable, &mut stats);
+    stats.report(options)?;
+    Ok(())
+}
+
+#[wasm_bindgen]
+pub fn sort_f32(values: &mut [f32], ascending: bool, options: &JsValue) -> Result<(), JsValue> {
+    console_log!(
+        "Sorting {} f32 values, ascending: {}",
+        values.len(),
+        ascending
+    );
+    let order = numeric::FloatOrder::from_options(options)?;
+    let algorithm = algorithms::SortAlgorithm::from_options(options)?;
+    let stable = algorithms::stable_from_options(options, algorithm, false)?;
+    let mut stats = algorithms::SortStats::new();
+    numeric::sort_floats(values, ascending, order, algorithm, stable, &mut stats);
+    stats.report(options)?;
+    Ok(())
+}
+
+#[wasm_bindgen]
+pub fn sort_i32(values: &mut [i32], ascending: bool, options: &JsValue) -> Result<(), JsValue> {
+    console_log!(
+        "Sorting {} i32 values, ascending: {}",
+        values.len(),
+        ascending
+    );
+    let algorithm = algorithms::SortAlgorithm::from_options(options)?;
+    let stable = algorithms::stable_from_options(options, algorithm, false)?;
+    let mut stats = algorithms::SortStats::new();
+    numeric::sort_integers(values, ascending, algorithm, stable, &mut stats);
+    stats.report(options)?;
+    Ok(())
 }
 
 #[wasm_bindgen]
-pub fn sort_strings(strings: &Array, ascending: bool) -> Array {
+pub fn sort_u32(values: &mut [u32], ascending: bool, options: &JsValue) -> Result<(), JsValue> {
+    console_log!(
+        "Sorting {} u32 values, ascending: {}",
+        values.len(),
+        ascending
+    );
+    let algorithm = algorithms::SortAlgorithm::from_options(options)?;
+    let stable = algorithms::stable_from_options(options, algorithm, false)?;
+    let mut stats = algorithms::SortStats::new();
+    numeric::sort_integers(values, ascending, algorithm, stable, &mut stats);
+    stats.report(options)?;
+    Ok(())
+}
+
+/// Sorts strings. `options.collation` selects `"binary"` (code point order,
+/// the default) or `"uca"` (Unicode Collation Algorithm, root locale) with an
+/// optional `options.strength` of `"base"`, `"accent"` or `"case"`. With
+/// `options.numeric`, runs of digits compare by their value in either
+/// collation, so "Set 2" sorts before "Set 10".
+///
+/// Elements that are not strings are reported as rejected, unless
+/// `options.coerce` converts them with `String()` first.
+#[wasm_bindgen]
+pub fn sort_strings(
+    strings: &Array,
+    ascending: bool,
+    options: &JsValue,
+) -> Result<Object, JsValue> {
     console_log!("Sorting strings, ascending: {}", ascending);
 
+    let order = collation::StringOrder::from_options(options)?;
+    let coerce = options::get_bool(options, "coerce")?.unwrap_or(false);
     let mut strs: Vec<String> = Vec::new();
+    let mut rejected: Vec<u32> = Vec::new();
 
     // Convert JS array to Rust vector
     for i in 0..strings.length() {
-        if let Some(s) = strings.get(i).as_string() {
-            strs.push(s);
+        let value = strings.get(i);
+        let s = if coerce {
+            to_string(&value).ok().map(String::from)
+        } else {
+            value.as_string()
+        };
+        match s {
+            Some(s) => strs.push(s),
+            None => rejected.push(i),
         }
     }
 
-    // Sort
+    // Sort positions rather than the strings themselves
+    let algorithm = algorithms::SortAlgorithm::comparison_from_options(options)?;
+    let stable = algorithms::stable_from_options(options, algorithm, true)?;
+    let mut stats = algorithms::SortStats::new();
+    let mut indices: Vec<usize> = (0..strs.len()).collect();
     if ascending {
-        strs.sort();
+        algorithms::sort_by(&mut indices, algorithm, stable, &mut stats, |&a, &b| {
+            order.compare(&strs[a], &strs[b])
+        });
     } else {
-        strs.sort_by(|a, b| b.cmp(a));
+        algorithms::sort_by(&mut indices, algorithm, stable, &mut stats, |&a, &b| {
+            order.compare(&strs[b], &strs[a])
+        });
     }
+    stats.report(options)?;
 
     // Convert back to JS array
     let result = Array::new();
-    for s in strs {
-        result.push(&JsValue::from_str(&s));
+    for index in indices {
+        result.push(&JsValue::from_str(&strs[index]));
     }
 
-    result
+    sort_report(&result, &rejected)
+}
+
+/// Sorts values that are not all numbers or strings, such as `Date`
+/// objects, BigInts and booleans. `options.type` is `"mixed"` by default,
+/// which orders booleans, then numbers and BigInts by value, then dates,
+/// then strings; `"date"`, `"bigint"` or `"boolean"` accept only that type.
+/// Other values go last, or first with `options.nulls: "first"`, and equal
+/// values keep their order.
+#[wasm_bindgen]
+pub fn sort_values(array: &Array, ascending: bool, options: &JsValue) -> Result<Array, JsValue> {
+    console_log!("Sorting values with array length: {}", array.length());
+
+    let key = keys::SortKey::identity_from_options(keys::KeyKind::Mixed, !ascending, options)?;
+    let algorithm = algorithms::SortAlgorithm::comparison_from_options(options)?;
+    let stable = algorithms::stable_from_options(options, algorithm, true)?;
+    let mut stats = algorithms::SortStats::new();
+    let items: Vec<JsValue> = array.iter().collect();
+    let indices =
+        keys::KeyTable::build(vec![key], &items).sorted_indices(algorithm, stable, &mut stats);
+    stats.report(options)?;
+
+    Ok(indices.into_iter().map(|index| &items[index]).collect())
+}
+
+// Argsort variants return the permutation that sorts the input instead of
+// the sorted values: `result[i]` is the original index of the i-th element.
+// Elements that cannot be read as the requested type are kept, after all
+// others, in their original order.
+
+fn argsort(
+    keys: Vec<keys::SortKey>,
+    array: &Array,
+    options: &JsValue,
+) -> Result<Vec<u32>, JsValue> {
+    let algorithm = algorithms::SortAlgorithm::comparison_from_options(options)?;
+    let stable = algorithms::stable_from_options(options, algorithm, true)?;
+    let mut stats = algorithms::SortStats::new();
+    let items: Vec<JsValue> = array.iter().collect();
+    let indices = keys::KeyTable::build(keys, &items).sorted_indices(algorithm, stable, &mut stats);
+    stats.report(options)?;
+
+    Ok(indices.into_iter().map(|index| index as u32).collect())
+}
+
+#[wasm_bindgen]
+pub fn argsort_numbers(
+    numbers: &Array,
+    ascending: bool,
+    options: &JsValue,
+) -> Result<Vec<u32>, JsValue> {
+    console_log!("Argsorting numbers, ascending: {}", ascending);
+    let mut key = keys::SortKey::identity(keys::KeyKind::Number, !ascending);
+    key.numbers = numeric::FloatOrder::from_options(options)?;
+    argsort(vec![key], numbers, options)
+}
+
+#[wasm_bindgen]
+pub fn argsort_strings(
+    strings: &Array,
+    ascending: bool,
+    options: &JsValue,
+) -> Result<Vec<u32>, JsValue> {
+    console_log!("Argsorting strings, ascending: {}", ascending);
+    let mut key = keys::SortKey::identity(keys::KeyKind::String, !ascending);
+    key.order = collation::StringOrder::from_options(options)?;
+    argsort(vec![key], strings, options)
+}
+
+#[wasm_bindgen]
+pub fn argsort_by_keys(
+    array: &Array,
+    spec: &JsValue,
+    options: &JsValue,
+) -> Result<Vec<u32>, JsValue> {
+    console_log!("Argsorting by keys with array length: {}", array.length());
+    argsort(keys::parse_spec(spec)?, array, options)
+}
+
+// Partial ordering. When only the first few elements of the sorted order
+// are needed, these avoid paying for a full sort. `spec` is the same key
+// specification `sort_by_keys` takes, and ties keep their input order. The
+// algorithm is fixed, so the `algorithm`, `stable` and `stats` sort options
+// do not apply.
+
+/// Returns the first `k` elements of the sorted order, using a bounded heap.
+#[wasm_bindgen]
+pub fn top_k(array: &Array, k: u32, spec: &JsValue) -> Result<Array, JsValue> {
+    console_log!("Selecting top {} of {} elements", k, array.length());
+
+    let items: Vec<JsValue> = array.iter().collect();
+    let table = keys::KeyTable::build(keys::parse_spec(spec)?, &items);
+
+    let result_array = Array::new();
+    for index in select::top_k_indices(&table, k as usize) {
+        result_array.push(&items[index]);
+    }
+    Ok(result_array)
+}
+
+/// Returns all elements with the first `k` in sorted order; the rest follow
+/// in no particular order.
+#[wasm_bindgen]
+pub fn partial_sort(array: &Array, k: u32, spec: &JsValue) -> Result<Array, JsValue> {
+    console_log!("Partially sorting {} of {} elements", k, array.length());
+
+    let items: Vec<JsValue> = array.iter().collect();
+    let table = keys::KeyTable::build(keys::parse_spec(spec)?, &items);
+
+    let result_array = Array::new();
+    for index in select::partial_sort_indices(&table, k as usize) {
+        result_array.push(&items[index]);
+    }
+    Ok(result_array)
+}
+
+/// Returns the element that would be at index `n` after sorting, or
+/// `undefined` if `n` is out of range.
+#[wasm_bindgen]
+pub fn select_nth(array: &Array, n: u32, spec: &JsValue) -> Result<JsValue, JsValue> {
+    console_log!("Selecting element {} of {}", n, array.length());
+
+    let items: Vec<JsValue> = array.iter().collect();
+    let table = keys::KeyTable::build(keys::parse_spec(spec)?, &items);
+
+    Ok(select::select_nth_index(&table, n as usize)
+        .map_or(JsValue::UNDEFINED, |index| items[index].clone()))
+}
+
+/// Returns the first `k` values of a typed array in sorted order, found
+/// with introselect. Takes the NaN and signed zero options of
+/// `sort_numbers`, but not the sort options.
+#[wasm_bindgen]
+pub fn top_k_f64(
+    values: &[f64],
+    k: u32,
+    ascending: bool,
+    options: &JsValue,
+) -> Result<Vec<f64>, JsValue> {
+    console_log!("Selecting top {} of {} f64 values", k, values.len());
+    let order = numeric::FloatOrder::from_options(options)?;
+    Ok(numeric::top_k_floats(values, k as usize, ascending, order))
+}
+
+/// Returns one page of the sorted order as `{ items, total, offset }`,
+/// where `items` holds at most `limit` elements starting at `offset` and
+/// `total` is the length of the whole array.
+///
+/// The permutation is cached, so later pages of the same array and spec
+/// are sliced without sorting again. The cache is keyed on the array's
+/// identity and length: after changing elements in place, pass
+/// `options.cache: false` or call `clear_sort_cache`. When a page comes from
+/// the cache, `options.stats` reports `cached: true` with no comparisons.
+#[wasm_bindgen]
+pub fn sort_page(
+    array: &Array,
+    spec: &JsValue,
+    offset: u32,
+    limit: u32,
+    options: &JsValue,
+) -> Result<Object, JsValue> {
+    console_log!(
+        "Sorting page at {} of {} with array length: {}",
+        offset,
+        limit,
+        array.length()
+    );
+
+    let algorithm = algorithms::SortAlgorithm::comparison_from_options(options)?;
+    let stable = algorithms::stable_from_options(options, algorithm, true)?;
+    let use_cache = options::get_bool(options, "cache")?.unwrap_or(true);
+    let mut stats = algorithms::SortStats::new();
+    let indices = page::sorted_order(array, spec, algorithm, stable, use_cache, &mut stats)?;
+    stats.report(options)?;
+
+    let start = (offset as usize).min(indices.len());
+    let end = start.saturating_add(limit as usize).min(indices.len());
+    let items = Array::new();
+    for &index in &indices[start..end] {
+        items.push(&array.get(index as u32));
+    }
+
+    let result = Object::new();
+    Reflect::set(&result, &JsValue::from_str("items"), &items)?;
+    Reflect::set(
+        &result,
+        &JsValue::from_str("total"),
+        &JsValue::from(array.length()),
+    )?;
+    Reflect::set(
+        &result,
+        &JsValue::from_str("offset"),
+        &JsValue::from(start as u32),
+    )?;
+    Ok(result)
+}
+
+/// Releases the permutation cached by `sort_page`.
+#[wasm_bindgen]
+pub fn clear_sort_cache() {
+    page::clear();
+}
+
+// Random permutations and samples, for feeding benchmarks reproducible
+// unsorted inputs. `seed` is a non-negative integer: the same seed always
+// gives the same result, and without one every call differs.
+
+/// Returns a shuffled copy of `array` (Fisher–Yates).
+#[wasm_bindgen]
+pub fn shuffle(array: &Array, seed: Option<f64>) -> Result<Array, JsValue> {
+    console_log!("Shuffling array length: {}", array.length());
+    let (mut rng, _) = rng::Rng::from_seed(seed)?;
+    let mut items: Vec<JsValue> = array.iter().collect();
+    sample::shuffle(&mut items, &mut rng);
+    Ok(items.into_iter().collect())
+}
+
+/// Shuffles a typed array in place.
+#[wasm_bindgen]
+pub fn shuffle_f64(values: &mut [f64], seed: Option<f64>) -> Result<(), JsValue> {
+    console_log!("Shuffling {} f64 values", values.len());
+    let (mut rng, _) = rng::Rng::from_seed(seed)?;
+    sample::shuffle(values, &mut rng);
+    Ok(())
+}
+
+/// Returns `k` distinct elements of `array` chosen uniformly at random, in
+/// random order, or all of them shuffled if `k` exceeds the length.
+#[wasm_bindgen]
+pub fn sample(array: &Array, k: u32, seed: Option<f64>) -> Result<Array, JsValue> {
+    console_log!("Sampling {} of {} elements", k, array.length());
+    let (mut rng, _) = rng::Rng::from_seed(seed)?;
+    Ok(
+        sample::sample_indices(array.length() as usize, k as usize, &mut rng)
+            .into_iter()
+            .map(|index| array.get(index as u32))
+            .collect(),
+    )
 }
 
 // Compute-intensive algorithms where WASM excels
@@ -142,18 +912,14 @@ pub fn monte_carlo_pi(iterations: u32) -> f64 {
     }
 
     let mut inside_circle = 0u32;
-    let mut rng_state = 12345u64;
+    let mut rng = rng::Rng::new(12345);
 
     // Debug: log first few values
     let mut debug_count = 0;
 
     for i in 0..iterations {
-        // Better LCG implementation
-        rng_state = rng_state.wrapping_mul(1103515245).wrapping_add(12345);
-        let x = (rng_state % 2147483647) as f64 / 2147483647.0 * 2.0 - 1.0;
-
-        rng_state = rng_state.wrapping_mul(1103515245).wrapping_add(12345);
-        let y = (rng_state % 2147483647) as f64 / 2147483647.0 * 2.0 - 1.0;
+        let x = rng.next_f64() * 2.0 - 1.0;
+        let y = rng.next_f64() * 2.0 - 1.0;
 
         if debug_count < 5 {
             console_log!(
@@ -0,0 +1,427 @@
+use std::cmp::Ordering;
+
+use wasm_bindgen::prelude::*;
+
+use crate::algorithms::{self, SortAlgorithm, SortStats};
+use crate::js_error;
+use crate::options::{get_bool, get_string};
+use crate::radix::{radix_sort, RadixKey};
+
+/// Below this many elements `SortAlgorithm::Auto` uses the comparison sort:
+/// radix sort reads the input once per key byte and needs a scratch copy,
+/// which does not pay off for small arrays.
+pub const RADIX_THRESHOLD: usize = 4096;
+
+fn use_radix(algorithm: SortAlgorithm, len: usize) -> bool {
+    match algorithm {
+        SortAlgorithm::Auto => len >= RADIX_THRESHOLD,
+        SortAlgorithm::Radix => true,
+        _ => false,
+    }
+}
+
+/// Floating point element types that can be sorted in place.
+pub trait Float: Copy + PartialOrd + RadixKey {
+    fn is_nan(self) -> bool;
+    /// True for both -0 and +0.
+    fn is_zero(self) -> bool;
+    fn total_cmp(self, other: Self) -> Ordering;
+}
+
+impl Float for f32 {
+    fn is_nan(self) -> bool {
+        f32::is_nan(self)
+    }
+
+    fn is_zero(self) -> bool {
+        self == 0.0
+    }
+
+    fn total_cmp(self, other: Self) -> Ordering {
+        f32::total_cmp(&self, &other)
+    }
+}
+
+impl Float for f64 {
+    fn is_nan(self) -> bool {
+        f64::is_nan(self)
+    }
+
+    fn is_zero(self) -> bool {
+        self == 0.0
+    }
+
+    fn total_cmp(self, other: Self) -> Ordering {
+        f64::total_cmp(&self, &other)
+    }
+}
+
+/// Where NaN and signed zeros go in a numeric sort. Infinities always sort
+/// as the smallest and largest numbers.
+#[derive(Clone, Copy, Default)]
+pub struct FloatOrder {
+    /// NaNs go before every number instead of after. Like null placement,
+    /// this does not depend on the sort direction.
+    pub nan_first: bool,
+    /// -0 sorts before +0 instead of comparing equal to it.
+    pub signed_zero: bool,
+}
+
+impl FloatOrder {
+    /// Reads `nan` (`"first"` or `"last"`) and `signedZero` from an options
+    /// object.
+    pub fn from_options(options: &JsValue) -> Result<FloatOrder, JsValue> {
+        let nan_first = match get_string(options, "nan")?.as_deref() {
+            None | Some("last") => false,
+            Some("first") => true,
+            Some(other) => {
+                return Err(js_error(&format!(
+                    "unknown NaN placement '{}', expected 'first' or 'last'",
+                    other
+                )))
+            }
+        };
+        let signed_zero = get_bool(options, "signedZero")?.unwrap_or(false);
+
+        Ok(FloatOrder {
+            nan_first,
+            signed_zero,
+        })
+    }
+
+    /// Compares two non-NaN numbers.
+    fn compare_numbers<T: Float>(&self, a: T, b: T) -> Ordering {
+        if self.signed_zero {
+            a.total_cmp(b)
+        } else {
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }
+    }
+
+    /// A total order over all values, NaN included: all NaNs compare equal
+    /// to each other and go to one end.
+    pub fn compare<T: Float>(&self, a: T, b: T, descending: bool) -> Ordering {
+        let nan_side = if self.nan_first {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        };
+        match (a.is_nan(), b.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => nan_side,
+            (false, true) => nan_side.reverse(),
+            (false, false) if descending => self.compare_numbers(b, a),
+            (false, false) => self.compare_numbers(a, b),
+        }
+    }
+}
+
+/// Moves every NaN to one end of the slice and returns the range holding
+/// the remaining numbers.
+fn partition_nans<T: Float>(values: &mut [T], nan_first: bool) -> std::ops::Range<usize> {
+    let mut len = 0;
+    for i in 0..values.len() {
+        if !values[i].is_nan() {
+            values.swap(len, i);
+            len += 1;
+        }
+    }
+
+    if nan_first {
+        values.rotate_left(len);
+        values.len() - len..values.len()
+    } else {
+        0..len
+    }
+}
+
+/// Sorts floats in place according to `order`. Only -0 and +0 can be equal
+/// without being identical, so `stable` only matters when signed zeros
+/// compare equal.
+pub fn sort_floats<T: Float>(
+    values: &mut [T],
+    ascending: bool,
+    order: FloatOrder,
+    algorithm: SortAlgorithm,
+    stable: bool,
+    stats: &mut SortStats,
+) {
+    let range = partition_nans(values, order.nan_first);
+    let numbers = &mut values[range];
+
+    if use_radix(algorithm, numbers.len()) {
+        // The key transform orders -0 before +0. When signed zeros compare
+        // equal, a stable sort must keep them in input order instead, so
+        // their signs are restored afterwards.
+        let restore_zeros = stable && !order.signed_zero;
+        let zeros: Vec<T> = if restore_zeros {
+            numbers.iter().copied().filter(|n| n.is_zero()).collect()
+        } else {
+            Vec::new()
+        };
+
+        let moves = radix_sort(numbers);
+        if !ascending {
+            numbers.reverse();
+        }
+        if restore_zeros {
+            if let Some(start) = numbers.iter().position(|n| n.is_zero()) {
+                numbers[start..start + zeros.len()].copy_from_slice(&zeros);
+            }
+        }
+
+        stats.algorithm = SortAlgorithm::Radix;
+        stats.moves = Some(moves);
+        stats.stable = stable || order.signed_zero;
+        return;
+    }
+
+    // Without NaNs a plain comparison is a total order
+    if ascending {
+        algorithms::sort_by(numbers, algorithm, stable, stats, |&a, &b| {
+            order.compare_numbers(a, b)
+        });
+    } else {
+        algorithms::sort_by(numbers, algorithm, stable, stats, |&a, &b| {
+            order.compare_numbers(b, a)
+        });
+    }
+}
+
+/// Returns the first `k` values of the sorted order, using introselect to
+/// split them off before sorting just those.
+pub fn top_k_floats<T: Float>(
+    values: &[T],
+    k: usize,
+    ascending: bool,
+    order: FloatOrder,
+) -> Vec<T> {
+    let mut values = values.to_vec();
+    let k = k.min(values.len());
+    let compare = |a: &T, b: &T| order.compare(*a, *b, !ascending);
+
+    if k < values.len() {
+        values.select_nth_unstable_by(k, compare);
+        values.truncate(k);
+    }
+    values.sort_unstable_by(compare);
+    values
+}
+
+/// Sorts integers in place. Equal integers are identical, so `stable` only
+/// selects which standard library sort runs.
+pub fn sort_integers<T: Ord + RadixKey>(
+    values: &mut [T],
+    ascending: bool,
+    algorithm: SortAlgorithm,
+    stable: bool,
+    stats: &mut SortStats,
+) {
+    if use_radix(algorithm, values.len()) {
+        let moves = radix_sort(values);
+        if !ascending {
+            values.reverse();
+        }
+        stats.algorithm = SortAlgorithm::Radix;
+        stats.moves = Some(moves);
+        stats.stable = true;
+    } else if ascending {
+        algorithms::sort_by(values, algorithm, stable, stats, |a, b| a.cmp(b));
+    } else {
+        algorithms::sort_by(values, algorithm, stable, stats, |a, b| b.cmp(a));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn random_floats(rng: &mut Rng, len: usize) -> Vec<f64> {
+        (0..len)
+            .map(|_| match rng.below(10) {
+                0 => f64::NAN,
+                1 => -0.0,
+                2 => 0.0,
+                3 => f64::INFINITY,
+                4 => f64::NEG_INFINITY,
+                _ => rng.below(2000) as f64 / 8.0 - 125.0,
+            })
+            .collect()
+    }
+
+    fn bits(values: &[f64]) -> Vec<u64> {
+        values.iter().map(|n| n.to_bits()).collect()
+    }
+
+    /// What a stable comparison sort under `order` gives.
+    fn expected(values: &[f64], ascending: bool, order: FloatOrder) -> Vec<f64> {
+        let mut expected = values.to_vec();
+        expected.sort_by(|a, b| order.compare(*a, *b, !ascending));
+        expected
+    }
+
+    #[test]
+    fn nans_go_to_one_end_in_either_direction() {
+        let values = [1.0, f64::NAN, -1.0, f64::NAN, 0.5];
+        for algorithm in [
+            SortAlgorithm::Auto,
+            SortAlgorithm::Merge,
+            SortAlgorithm::Radix,
+        ] {
+            for ascending in [true, false] {
+                for nan_first in [false, true] {
+                    let order = FloatOrder {
+                        nan_first,
+                        signed_zero: false,
+                    };
+                    let mut sorted = values.to_vec();
+                    sort_floats(
+                        &mut sorted,
+                        ascending,
+                        order,
+                        algorithm,
+                        true,
+                        &mut SortStats::new(),
+                    );
+                    let (nans, numbers) = if nan_first {
+                        sorted.split_at(2)
+                    } else {
+                        let (numbers, nans) = sorted.split_at(3);
+                        (nans, numbers)
+                    };
+                    assert!(nans.iter().all(|n| n.is_nan()));
+                    let numbers_expected = if ascending {
+                        [-1.0, 0.5, 1.0]
+                    } else {
+                        [1.0, 0.5, -1.0]
+                    };
+                    assert_eq!(numbers, numbers_expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn signed_zero_orders_negative_zero_first() {
+        let values = [0.0, -0.0, 1.0, -0.0, 0.0, -1.0];
+        let order = FloatOrder {
+            nan_first: false,
+            signed_zero: true,
+        };
+        for algorithm in [
+            SortAlgorithm::Auto,
+            SortAlgorithm::Heap,
+            SortAlgorithm::Radix,
+        ] {
+            let mut sorted = values.to_vec();
+            sort_floats(
+                &mut sorted,
+                true,
+                order,
+                algorithm,
+                false,
+                &mut SortStats::new(),
+            );
+            assert_eq!(bits(&sorted), bits(&[-1.0, -0.0, -0.0, 0.0, 0.0, 1.0]));
+            sort_floats(
+                &mut sorted,
+                false,
+                order,
+                algorithm,
+                false,
+                &mut SortStats::new(),
+            );
+            assert_eq!(bits(&sorted), bits(&[1.0, 0.0, 0.0, -0.0, -0.0, -1.0]));
+        }
+    }
+
+    #[test]
+    fn radix_path_matches_a_stable_comparison_sort() {
+        let mut rng = Rng::new(17);
+        // Still above the threshold once the NaNs are set aside
+        let values = random_floats(&mut rng, 2 * RADIX_THRESHOLD);
+        for ascending in [true, false] {
+            for nan_first in [false, true] {
+                for signed_zero in [false, true] {
+                    let order = FloatOrder {
+                        nan_first,
+                        signed_zero,
+                    };
+                    let mut sorted = values.clone();
+                    let mut stats = SortStats::new();
+                    sort_floats(
+                        &mut sorted,
+                        ascending,
+                        order,
+                        SortAlgorithm::Auto,
+                        true,
+                        &mut stats,
+                    );
+                    assert!(stats.algorithm == SortAlgorithm::Radix);
+                    assert!(stats.stable);
+                    // Without `signedZero`, the zeros keep their input order
+                    // and signs through the restore step
+                    assert_eq!(bits(&sorted), bits(&expected(&values, ascending, order)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unstable_radix_sort_without_signed_zero_groups_zeros() {
+        let mut rng = Rng::new(19);
+        let values = random_floats(&mut rng, 2 * RADIX_THRESHOLD);
+        let order = FloatOrder::default();
+        let mut sorted = values.clone();
+        let mut stats = SortStats::new();
+        sort_floats(
+            &mut sorted,
+            true,
+            order,
+            SortAlgorithm::Auto,
+            false,
+            &mut stats,
+        );
+        assert!(stats.algorithm == SortAlgorithm::Radix && !stats.stable);
+        assert!(sorted
+            .windows(2)
+            .all(|w| order.compare(w[0], w[1], false).is_le()));
+        let mut sorted_bits = bits(&sorted);
+        let mut input_bits = bits(&values);
+        sorted_bits.sort_unstable();
+        input_bits.sort_unstable();
+        assert_eq!(sorted_bits, input_bits);
+    }
+
+    #[test]
+    fn comparison_path_matches_the_standard_sort() {
+        let mut rng = Rng::new(23);
+        for len in [0, 1, 2, 50, 500] {
+            let values = random_floats(&mut rng, len);
+            for ascending in [true, false] {
+                let order = FloatOrder::default();
+                let mut sorted = values.clone();
+                sort_floats(
+                    &mut sorted,
+                    ascending,
+                    order,
+                    SortAlgorithm::Merge,
+                    true,
+                    &mut SortStats::new(),
+                );
+                assert_eq!(bits(&sorted), bits(&expected(&values, ascending, order)));
+            }
+        }
+    }
+
+    #[test]
+    fn top_k_takes_the_first_values_of_the_order() {
+        let values = [3.0, f64::NAN, -1.0, 2.0, -0.0];
+        let order = FloatOrder::default();
+        assert_eq!(top_k_floats(&values, 2, true, order), [-1.0, -0.0]);
+        assert_eq!(top_k_floats(&values, 2, false, order), [3.0, 2.0]);
+        assert_eq!(top_k_floats(&values, usize::MAX, false, order).len(), 5);
+        assert!(top_k_floats(&values, 5, true, order)[4].is_nan());
+    }
+}
@@ -0,0 +1,48 @@
+use js_sys::Reflect;
+use wasm_bindgen::prelude::*;
+
+use crate::js_error;
+
+/// Reads a property from an options object. Missing options objects and
+/// `undefined`/`null` properties both read as `None`.
+fn get(object: &JsValue, name: &str) -> Result<Option<JsValue>, JsValue> {
+    if object.is_undefined() || object.is_null() {
+        return Ok(None);
+    }
+    let value = Reflect::get(object, &JsValue::from_str(name))?;
+    if value.is_undefined() || value.is_null() {
+        Ok(None)
+    } else {
+        Ok(Some(value))
+    }
+}
+
+pub fn get_string(object: &JsValue, name: &str) -> Result<Option<String>, JsValue> {
+    match get(object, name)? {
+        None => Ok(None),
+        Some(value) => value
+            .as_string()
+            .map(Some)
+            .ok_or_else(|| js_error(&format!("option '{}' must be a string", name))),
+    }
+}
+
+pub fn get_bool(object: &JsValue, name: &str) -> Result<Option<bool>, JsValue> {
+    match get(object, name)? {
+        None => Ok(None),
+        Some(value) => value
+            .as_bool()
+            .map(Some)
+            .ok_or_else(|| js_error(&format!("option '{}' must be a boolean", name))),
+    }
+}
+
+pub fn get_number(object: &JsValue, name: &str) -> Result<Option<f64>, JsValue> {
+    match get(object, name)? {
+        None => Ok(None),
+        Some(value) => value
+            .as_f64()
+            .map(Some)
+            .ok_or_else(|| js_error(&format!("option '{}' must be a number", name))),
+    }
+}
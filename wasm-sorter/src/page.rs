@@ -0,0 +1,189 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::{Array, Object, JSON};
+use wasm_bindgen::prelude::*;
+
+use crate::algorithms::{SortAlgorithm, SortStats};
+use crate::keys::{self, KeyTable};
+
+/// The permutation computed by the last `sort_page` call, kept so that
+/// paging through the same array with the same specification sorts once.
+struct CachedOrder {
+    array: JsValue,
+    len: u32,
+    spec: String,
+    algorithm: SortAlgorithm,
+    stable: bool,
+    /// The algorithm that ran and whether it was stable, reported again on
+    /// a hit.
+    ran: (SortAlgorithm, bool),
+    indices: Rc<Vec<usize>>,
+}
+
+/// Holds at most one permutation, that of the last sort.
+#[derive(Default)]
+struct OrderCache {
+    entry: Option<CachedOrder>,
+}
+
+impl OrderCache {
+    /// The cached permutation if it was computed for an array that
+    /// `same_array` accepts, of length `len`, with the same spec, algorithm
+    /// and stability. A hit is recorded in `stats`, with the algorithm that
+    /// produced the permutation and no comparisons.
+    fn get(
+        &self,
+        same_array: impl Fn(&JsValue) -> bool,
+        len: u32,
+        spec: &str,
+        algorithm: SortAlgorithm,
+        stable: bool,
+        stats: &mut SortStats,
+    ) -> Option<Rc<Vec<usize>>> {
+        let cached = self.entry.as_ref().filter(|cached| {
+            cached.len == len
+                && cached.spec == spec
+                && cached.algorithm == algorithm
+                && cached.stable == stable
+                && same_array(&cached.array)
+        })?;
+        (stats.algorithm, stats.stable) = cached.ran;
+        stats.cached = true;
+        Some(Rc::clone(&cached.indices))
+    }
+}
+
+thread_local! {
+    static CACHE: RefCell<OrderCache> = RefCell::default();
+}
+
+/// A string that identifies a specification, so that an equal spec passed
+/// as a fresh object still hits the cache.
+fn spec_fingerprint(spec: &JsValue) -> Result<String, JsValue> {
+    match spec.as_string() {
+        Some(text) => Ok(text),
+        None => Ok(JSON::stringify(spec)?.into()),
+    }
+}
+
+/// Returns the sorted order of `array`, reusing the cached permutation when
+/// the same array (by identity and length) is sorted with the same spec,
+/// algorithm and stability. On a hit `stats` says so instead of counting a
+/// sort that did not run.
+pub fn sorted_order(
+    array: &Array,
+    spec: &JsValue,
+    algorithm: SortAlgorithm,
+    stable: bool,
+    use_cache: bool,
+    stats: &mut SortStats,
+) -> Result<Rc<Vec<usize>>, JsValue> {
+    let fingerprint = spec_fingerprint(spec)?;
+    if use_cache {
+        let cached = CACHE.with(|cache| {
+            cache.borrow().get(
+                |cached| Object::is(cached, array),
+                array.length(),
+                &fingerprint,
+                algorithm,
+                stable,
+                stats,
+            )
+        });
+        if let Some(indices) = cached {
+            return Ok(indices);
+        }
+    }
+
+    let items: Vec<JsValue> = array.iter().collect();
+    let table = KeyTable::build(keys::parse_spec(spec)?, &items);
+    let indices = Rc::new(table.sorted_indices(algorithm, stable, stats));
+
+    if use_cache {
+        CACHE.with(|cache| {
+            cache.borrow_mut().entry = Some(CachedOrder {
+                array: array.into(),
+                len: array.length(),
+                spec: fingerprint,
+                algorithm,
+                stable,
+                ran: (stats.algorithm, stats.stable),
+                indices: Rc::clone(&indices),
+            })
+        });
+    }
+    Ok(indices)
+}
+
+/// Drops the cached permutation and the array reference it holds.
+pub fn clear() {
+    CACHE.with(|cache| cache.borrow_mut().entry.take());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_second_call_reports_the_cached_sort() {
+        // A first call that ran pdqsort on a 3 element array
+        let cache = OrderCache {
+            entry: Some(CachedOrder {
+                array: JsValue::NULL,
+                len: 3,
+                spec: "albumId, id".to_string(),
+                algorithm: SortAlgorithm::Pdq,
+                stable: false,
+                ran: (SortAlgorithm::Pdq, false),
+                indices: Rc::new(vec![2, 0, 1]),
+            }),
+        };
+
+        let mut stats = SortStats::new();
+        let hit = cache.get(
+            |_| true,
+            3,
+            "albumId, id",
+            SortAlgorithm::Pdq,
+            false,
+            &mut stats,
+        );
+        assert_eq!(hit.as_deref(), Some(&vec![2, 0, 1]));
+        assert!(stats.cached);
+        assert!(stats.algorithm == SortAlgorithm::Pdq && !stats.stable);
+        assert_eq!(stats.comparisons, 0);
+
+        let mut stats = SortStats::new();
+        for miss in [
+            cache.get(
+                |_| false,
+                3,
+                "albumId, id",
+                SortAlgorithm::Pdq,
+                false,
+                &mut stats,
+            ),
+            cache.get(
+                |_| true,
+                4,
+                "albumId, id",
+                SortAlgorithm::Pdq,
+                false,
+                &mut stats,
+            ),
+            cache.get(|_| true, 3, "id", SortAlgorithm::Pdq, false, &mut stats),
+            cache.get(
+                |_| true,
+                3,
+                "albumId, id",
+                SortAlgorithm::Heap,
+                false,
+                &mut stats,
+            ),
+        ] {
+            assert!(miss.is_none());
+        }
+        assert!(!stats.cached);
+    }
+}
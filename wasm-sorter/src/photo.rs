@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::algorithms::{self, SortAlgorithm, SortStats};
+use crate::collation::StringOrder;
+use crate::js_error;
+
+/// A photo record as served by JSONPlaceholder, mirroring the `Photo`
+/// interface of the frontend.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Photo {
+    pub album_id: u32,
+    pub id: u32,
+    pub title: String,
+    pub url: String,
+    pub thumbnail_url: String,
+}
+
+/// The fields photos can be sorted by.
+#[derive(Clone, Copy, PartialEq)]
+pub enum PhotoField {
+    Id,
+    AlbumId,
+    Title,
+    /// The title's length in UTF-16 code units, like `title.length`.
+    TitleLength,
+}
+
+impl PhotoField {
+    pub fn parse(name: &str) -> Result<PhotoField, JsValue> {
+        match name {
+            "id" => Ok(PhotoField::Id),
+            "albumId" => Ok(PhotoField::AlbumId),
+            "title" => Ok(PhotoField::Title),
+            "titleLength" => Ok(PhotoField::TitleLength),
+            other => Err(js_error(&format!(
+                "unknown photo field '{}', expected one of 'id', 'albumId', 'title' or 'titleLength'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Sorts photos by one field. Titles are compared with `order`.
+pub fn sort(
+    mut photos: Vec<Photo>,
+    field: PhotoField,
+    descending: bool,
+    order: &StringOrder,
+    algorithm: SortAlgorithm,
+    stable: bool,
+    stats: &mut SortStats,
+) -> Vec<Photo> {
+    // Counted once rather than on every comparison
+    let lengths: Vec<usize> = if field == PhotoField::TitleLength {
+        photos
+            .iter()
+            .map(|photo| photo.title.encode_utf16().count())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut indices: Vec<usize> = (0..photos.len()).collect();
+    algorithms::sort_by(&mut indices, algorithm, stable, stats, |&a, &b| {
+        let (x, y) = (&photos[a], &photos[b]);
+        let ordering = match field {
+            PhotoField::Id => x.id.cmp(&y.id),
+            PhotoField::AlbumId => x.album_id.cmp(&y.album_id),
+            PhotoField::Title => order.compare(&x.title, &y.title),
+            PhotoField::TitleLength => lengths[a].cmp(&lengths[b]),
+        };
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+
+    indices
+        .into_iter()
+        .map(|index| std::mem::take(&mut photos[index]))
+        .collect()
+}
@@ -0,0 +1,266 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+use crate::js_error;
+use crate::keys::{KeyValue, SortKey};
+use crate::numeric::FloatOrder;
+use crate::options::get_string;
+
+struct Node {
+    priority: f64,
+    /// Insertion counter, so that equal priorities come out first in,
+    /// first out.
+    sequence: u64,
+    payload: JsValue,
+    max: bool,
+}
+
+impl Ord for Node {
+    /// `BinaryHeap` pops the greatest node, so the node that should come out
+    /// first compares greater. NaN priorities always come out last.
+    fn cmp(&self, other: &Self) -> Ordering {
+        FloatOrder::default()
+            .compare(other.priority, self.priority, self.max)
+            .then(other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Node {}
+
+/// A priority queue backed by `BinaryHeap`, holding numeric priorities with
+/// arbitrary JS payloads.
+///
+/// `options.order` is `"min"` (the default) to pop the smallest priority
+/// first or `"max"` for the largest. With `options.key`, a property path,
+/// `push_item` and `from_array` read each element's priority from that
+/// property instead of taking it separately.
+#[wasm_bindgen]
+pub struct PriorityQueue {
+    heap: BinaryHeap<Node>,
+    key: Option<SortKey>,
+    max: bool,
+    sequence: u64,
+}
+
+impl PriorityQueue {
+    fn node(&mut self, priority: f64, payload: JsValue) -> Node {
+        self.sequence += 1;
+        Node {
+            priority,
+            sequence: self.sequence,
+            payload,
+            max: self.max,
+        }
+    }
+
+    fn priority_of(&self, item: &JsValue) -> Result<f64, JsValue> {
+        match &self.key {
+            Some(key) => match key.extract(item) {
+                KeyValue::Number(priority) => Ok(priority),
+                _ => Err(js_error(&format!(
+                    "element has no numeric priority at '{}'",
+                    key.path.join(".")
+                ))),
+            },
+            None => item
+                .as_f64()
+                .ok_or_else(|| js_error("element must be a number when no 'key' is set")),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl PriorityQueue {
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: &JsValue) -> Result<PriorityQueue, JsValue> {
+        let max = match get_string(options, "order")?.as_deref() {
+            None | Some("min") => false,
+            Some("max") => true,
+            Some(other) => {
+                return Err(js_error(&format!(
+                    "unknown queue order '{}', expected 'min' or 'max'",
+                    other
+                )))
+            }
+        };
+        let key = match get_string(options, "key")? {
+            Some(path) => Some(SortKey::new(&path)?),
+            None => None,
+        };
+
+        Ok(PriorityQueue {
+            heap: BinaryHeap::new(),
+            key,
+            max,
+            sequence: 0,
+        })
+    }
+
+    /// Builds a queue from an array in O(n). Each element is its own
+    /// payload; its priority is read from `options.key`, or is the element
+    /// itself when there is no key.
+    pub fn from_array(array: &Array, options: &JsValue) -> Result<PriorityQueue, JsValue> {
+        let mut queue = PriorityQueue::new(options)?;
+        let mut nodes = Vec::with_capacity(array.length() as usize);
+        for item in array.iter() {
+            let priority = queue.priority_of(&item)?;
+            nodes.push(queue.node(priority, item));
+        }
+        queue.heap = BinaryHeap::from(nodes);
+        Ok(queue)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> u32 {
+        self.heap.len() as u32
+    }
+
+    pub fn push(&mut self, priority: f64, payload: JsValue) {
+        let node = self.node(priority, payload);
+        self.heap.push(node);
+    }
+
+    /// Pushes an element with the priority read like `from_array` does.
+    pub fn push_item(&mut self, item: JsValue) -> Result<(), JsValue> {
+        let priority = self.priority_of(&item)?;
+        self.push(priority, item);
+        Ok(())
+    }
+
+    /// Removes and returns the payload that comes out first, or `undefined`
+    /// if the queue is empty.
+    pub fn pop(&mut self) -> JsValue {
+        self.heap
+            .pop()
+            .map_or(JsValue::UNDEFINED, |node| node.payload)
+    }
+
+    pub fn peek(&self) -> JsValue {
+        self.heap
+            .peek()
+            .map_or(JsValue::UNDEFINED, |node| node.payload.clone())
+    }
+
+    pub fn peek_priority(&self) -> Option<f64> {
+        self.heap.peek().map(|node| node.priority)
+    }
+
+    /// Empties the queue, returning the payloads in the order `pop` would
+    /// have returned them.
+    pub fn drain_sorted(&mut self) -> Array {
+        let result_array = Array::new();
+        // The sorted vector is ascending by `Node`'s order, whose greatest
+        // node is the first out
+        for node in std::mem::take(&mut self.heap)
+            .into_sorted_vec()
+            .into_iter()
+            .rev()
+        {
+            result_array.push(&node.payload);
+        }
+        result_array
+    }
+
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(max: bool) -> PriorityQueue {
+        PriorityQueue {
+            heap: BinaryHeap::new(),
+            key: None,
+            max,
+            sequence: 0,
+        }
+    }
+
+    /// Pops every node, returning the priorities and push sequences in the
+    /// order they come out.
+    fn drain(queue: &mut PriorityQueue) -> Vec<(f64, u64)> {
+        std::iter::from_fn(|| queue.heap.pop())
+            .map(|node| (node.priority, node.sequence))
+            .collect()
+    }
+
+    fn push_all(queue: &mut PriorityQueue, priorities: &[f64]) {
+        for &priority in priorities {
+            queue.push(priority, JsValue::NULL);
+        }
+    }
+
+    #[test]
+    fn equal_priorities_come_out_first_in_first_out() {
+        for max in [false, true] {
+            let mut queue = queue(max);
+            push_all(&mut queue, &[1.0, 1.0, 1.0, 1.0]);
+            let sequences: Vec<u64> = drain(&mut queue).into_iter().map(|(_, s)| s).collect();
+            assert_eq!(sequences, [1, 2, 3, 4]);
+        }
+    }
+
+    #[test]
+    fn min_and_max_queues_pop_opposite_ends() {
+        let priorities = [3.0, -1.0, 2.0, 3.0, 0.5];
+        let mut min = queue(false);
+        push_all(&mut min, &priorities);
+        assert_eq!(
+            drain(&mut min),
+            [(-1.0, 2), (0.5, 5), (2.0, 3), (3.0, 1), (3.0, 4)]
+        );
+        let mut max = queue(true);
+        push_all(&mut max, &priorities);
+        assert_eq!(
+            drain(&mut max),
+            [(3.0, 1), (3.0, 4), (2.0, 3), (0.5, 5), (-1.0, 2)]
+        );
+    }
+
+    #[test]
+    fn nan_priorities_come_out_last() {
+        for max in [false, true] {
+            let mut queue = queue(max);
+            push_all(&mut queue, &[f64::NAN, 2.0, f64::NAN, 1.0]);
+            let popped = drain(&mut queue);
+            assert!(!popped[0].0.is_nan() && !popped[1].0.is_nan());
+            assert!(popped[2].0.is_nan() && popped[3].0.is_nan());
+            assert_eq!((popped[2].1, popped[3].1), (1, 3));
+        }
+    }
+
+    #[test]
+    fn drain_order_matches_pop_order() {
+        let priorities = [2.0, f64::NAN, 1.0, 2.0, 1.0];
+        let mut drained = queue(false);
+        push_all(&mut drained, &priorities);
+        let sorted: Vec<u64> = std::mem::take(&mut drained.heap)
+            .into_sorted_vec()
+            .into_iter()
+            .rev()
+            .map(|node| node.sequence)
+            .collect();
+        let mut popped = queue(false);
+        push_all(&mut popped, &priorities);
+        let popped: Vec<u64> = drain(&mut popped).into_iter().map(|(_, s)| s).collect();
+        assert_eq!(sorted, popped);
+    }
+}
@@ -0,0 +1,195 @@
+/// Element types with an order-preserving mapping to unsigned integers, so
+/// that sorting the keys as plain bytes sorts the values.
+pub trait RadixKey: Copy {
+    /// Number of significant bytes in `key`.
+    const BYTES: usize;
+
+    fn key(self) -> u64;
+}
+
+impl RadixKey for u32 {
+    const BYTES: usize = 4;
+
+    fn key(self) -> u64 {
+        self as u64
+    }
+}
+
+impl RadixKey for i32 {
+    const BYTES: usize = 4;
+
+    fn key(self) -> u64 {
+        // Flipping the sign bit moves negative numbers below positive ones
+        ((self as u32) ^ 0x8000_0000) as u64
+    }
+}
+
+impl RadixKey for f32 {
+    const BYTES: usize = 4;
+
+    fn key(self) -> u64 {
+        // Negative floats have all bits flipped so that larger magnitudes
+        // sort first; positive floats only get the sign bit set
+        let bits = self.to_bits();
+        let key = if bits & 0x8000_0000 != 0 {
+            !bits
+        } else {
+            bits | 0x8000_0000
+        };
+        key as u64
+    }
+}
+
+impl RadixKey for f64 {
+    const BYTES: usize = 8;
+
+    fn key(self) -> u64 {
+        let bits = self.to_bits();
+        if bits & 0x8000_0000_0000_0000 != 0 {
+            !bits
+        } else {
+            bits | 0x8000_0000_0000_0000
+        }
+    }
+}
+
+/// Ascending LSD radix sort, one byte per pass. Passes where every element
+/// has the same byte are skipped. Returns the number of element writes.
+pub fn radix_sort<T: RadixKey>(values: &mut [T]) -> u64 {
+    let len = values.len();
+    if len < 2 {
+        return 0;
+    }
+
+    // Histograms for every byte are built in a single read of the input
+    let mut counts = vec![[0usize; 256]; T::BYTES];
+    for value in values.iter() {
+        let key = value.key();
+        for (byte, histogram) in counts.iter_mut().enumerate() {
+            histogram[((key >> (byte * 8)) & 0xff) as usize] += 1;
+        }
+    }
+
+    let mut scratch = values.to_vec();
+    let mut in_scratch = false;
+    let mut moves = 0;
+
+    for (byte, histogram) in counts.iter().enumerate() {
+        if histogram.contains(&len) {
+            continue;
+        }
+
+        let mut offsets = [0usize; 256];
+        let mut total = 0;
+        for (offset, &count) in offsets.iter_mut().zip(histogram.iter()) {
+            *offset = total;
+            total += count;
+        }
+
+        let (src, dst) = if in_scratch {
+            (&scratch[..], &mut values[..])
+        } else {
+            (&values[..], &mut scratch[..])
+        };
+        for &value in src {
+            let digit = ((value.key() >> (byte * 8)) & 0xff) as usize;
+            dst[offsets[digit]] = value;
+            offsets[digit] += 1;
+        }
+        in_scratch = !in_scratch;
+        moves += len as u64;
+    }
+
+    if in_scratch {
+        values.copy_from_slice(&scratch);
+        moves += len as u64;
+    }
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    /// Whether `key` orders `values`, which must be ascending, the same way.
+    fn keys_ascend<T: RadixKey>(values: &[T]) -> bool {
+        values.windows(2).all(|w| w[0].key() < w[1].key())
+    }
+
+    #[test]
+    fn integer_keys_preserve_order() {
+        assert!(keys_ascend(&[0u32, 1, 255, 256, 0x8000_0000, u32::MAX]));
+        assert!(keys_ascend(&[i32::MIN, -256, -1, 0, 1, 255, i32::MAX]));
+    }
+
+    #[test]
+    fn float_keys_preserve_order() {
+        assert!(keys_ascend(&[
+            f64::NEG_INFINITY,
+            f64::MIN,
+            -1.0,
+            -f64::MIN_POSITIVE,
+            -5e-324,
+            -0.0,
+            0.0,
+            5e-324,
+            f64::MIN_POSITIVE,
+            1.0,
+            f64::MAX,
+            f64::INFINITY,
+            f64::NAN,
+        ]));
+        assert!(keys_ascend(&[
+            f32::NEG_INFINITY,
+            -1.0f32,
+            -1e-45,
+            -0.0,
+            0.0,
+            1e-45,
+            1.0,
+            f32::INFINITY,
+            f32::NAN,
+        ]));
+    }
+
+    #[test]
+    fn radix_sort_matches_the_standard_sort() {
+        let mut rng = Rng::new(5);
+        for len in [0, 1, 2, 100, 1000] {
+            let mut ints: Vec<i32> = (0..len).map(|_| rng.next_u64() as i32).collect();
+            let mut expected = ints.clone();
+            expected.sort();
+            radix_sort(&mut ints);
+            assert_eq!(ints, expected);
+
+            // Only the high bytes vary, so the low byte passes are skipped
+            let mut words: Vec<u32> = (0..len).map(|_| (rng.below(4) as u32) << 24).collect();
+            let mut expected = words.clone();
+            expected.sort();
+            radix_sort(&mut words);
+            assert_eq!(words, expected);
+
+            let mut floats: Vec<f64> = (0..len)
+                .map(|_| match rng.below(5) {
+                    0 => -0.0,
+                    1 => 0.0,
+                    _ => (rng.next_u64() as i64) as f64 / 1e6,
+                })
+                .collect();
+            let mut expected = floats.clone();
+            expected.sort_by(f64::total_cmp);
+            radix_sort(&mut floats);
+            let bits = |v: &[f64]| v.iter().map(|n| n.to_bits()).collect::<Vec<_>>();
+            assert_eq!(bits(&floats), bits(&expected));
+        }
+    }
+
+    #[test]
+    fn radix_sort_of_equal_values_moves_nothing() {
+        let mut values = vec![42u32; 100];
+        assert_eq!(radix_sort(&mut values), 0);
+        assert_eq!(radix_sort(&mut [1u32]), 0);
+        assert_eq!(radix_sort::<u32>(&mut []), 0);
+    }
+}
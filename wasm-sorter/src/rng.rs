@@ -0,0 +1,114 @@
+use wasm_bindgen::prelude::*;
+
+use crate::js_error;
+use crate::options::get_number;
+
+/// `Number.MAX_SAFE_INTEGER`, the largest seed that survives the trip
+/// through a JS number.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+/// xoshiro256** seeded through SplitMix64. Fast, small, and the same on
+/// every platform, so a seed reproduces a result exactly.
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        let mut mix = seed;
+        let mut next = || {
+            mix = mix.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = mix;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        Rng {
+            state: [next(), next(), next(), next()],
+        }
+    }
+
+    /// Creates a generator from a JS seed, a non-negative integer. Without
+    /// one a seed is drawn from `Math.random()`. Returns the seed too, so
+    /// callers can report it and a run can be repeated.
+    pub fn from_seed(seed: Option<f64>) -> Result<(Rng, u64), JsValue> {
+        let seed = match seed {
+            Some(seed) if seed >= 0.0 && seed.fract() == 0.0 && seed <= MAX_SAFE_INTEGER => {
+                seed as u64
+            }
+            Some(seed) => {
+                return Err(js_error(&format!(
+                    "seed must be a non-negative safe integer, got {}",
+                    seed
+                )))
+            }
+            None => (js_sys::Math::random() * MAX_SAFE_INTEGER) as u64,
+        };
+        Ok((Rng::new(seed), seed))
+    }
+
+    /// Like `from_seed`, reading the seed from `options.seed`.
+    pub fn from_options(options: &JsValue) -> Result<(Rng, u64), JsValue> {
+        Rng::from_seed(get_number(options, "seed")?)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.state;
+        let result = s1.wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = *s1 << 17;
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+        result
+    }
+
+    /// A uniform float in `[0, 1)`, from the top 53 bits of the next
+    /// output.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A uniform integer in `0..bound`, without modulo bias (Lemire's
+    /// method). `bound` must not be zero.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let product = u128::from(self.next_u64()) * u128::from(bound);
+            if (product as u64) >= threshold {
+                return (product >> 64) as u64;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_seed_reproduces_the_sequence() {
+        let draw = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..16).map(|_| rng.next_u64()).collect::<Vec<_>>()
+        };
+        assert_eq!(draw(42), draw(42));
+        assert_ne!(draw(42), draw(43));
+    }
+
+    #[test]
+    fn draws_stay_in_range() {
+        let mut rng = Rng::new(9);
+        for bound in [1, 2, 3, 7, 1000, u64::MAX] {
+            for _ in 0..200 {
+                assert!(rng.below(bound) < bound);
+            }
+        }
+        for _ in 0..1000 {
+            let x = rng.next_f64();
+            assert!((0.0..1.0).contains(&x));
+        }
+    }
+}
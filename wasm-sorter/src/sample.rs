@@ -0,0 +1,144 @@
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+use crate::rng::Rng;
+
+/// Fisher–Yates shuffle: every permutation is equally likely.
+pub fn shuffle<T>(values: &mut [T], rng: &mut Rng) {
+    for i in (1..values.len()).rev() {
+        let j = rng.below(i as u64 + 1) as usize;
+        values.swap(i, j);
+    }
+}
+
+/// Picks `k` distinct indices below `len` uniformly, in random order, by
+/// running the first `k` steps of a Fisher–Yates shuffle.
+pub fn sample_indices(len: usize, k: usize, rng: &mut Rng) -> Vec<usize> {
+    let k = k.min(len);
+    let mut indices: Vec<usize> = (0..len).collect();
+    for i in 0..k {
+        let j = i + rng.below((len - i) as u64) as usize;
+        indices.swap(i, j);
+    }
+    indices.truncate(k);
+    indices
+}
+
+/// Where Algorithm R keeps the `seen`-th element of a stream in a reservoir
+/// of `capacity` elements, or `None` to drop it.
+fn reservoir_slot(seen: u64, capacity: usize, rng: &mut Rng) -> Option<usize> {
+    if seen <= capacity as u64 {
+        return Some(seen as usize - 1);
+    }
+    let j = rng.below(seen) as usize;
+    (j < capacity).then_some(j)
+}
+
+/// A uniform sample of fixed size from a stream of elements whose length is
+/// not known in advance (reservoir sampling, Algorithm R). Elements can be
+/// pushed one at a time or in batches; the sample is valid at any point.
+#[wasm_bindgen]
+pub struct Reservoir {
+    items: Vec<JsValue>,
+    capacity: usize,
+    seen: u64,
+    rng: Rng,
+}
+
+#[wasm_bindgen]
+impl Reservoir {
+    /// Creates a reservoir keeping `k` elements. The same seed and pushes
+    /// always give the same sample.
+    #[wasm_bindgen(constructor)]
+    pub fn new(k: u32, seed: Option<f64>) -> Result<Reservoir, JsValue> {
+        let (rng, _) = Rng::from_seed(seed)?;
+        Ok(Reservoir {
+            items: Vec::with_capacity(k as usize),
+            capacity: k as usize,
+            seen: 0,
+            rng,
+        })
+    }
+
+    /// The number of elements pushed so far.
+    #[wasm_bindgen(getter)]
+    pub fn seen(&self) -> f64 {
+        self.seen as f64
+    }
+
+    pub fn push(&mut self, item: JsValue) {
+        self.seen += 1;
+        match reservoir_slot(self.seen, self.capacity, &mut self.rng) {
+            Some(slot) if slot == self.items.len() => self.items.push(item),
+            Some(slot) => self.items[slot] = item,
+            None => {}
+        }
+    }
+
+    pub fn push_all(&mut self, array: &Array) {
+        for item in array.iter() {
+            self.push(item);
+        }
+    }
+
+    /// Returns the current sample, in no particular order.
+    pub fn sample(&self) -> Array {
+        self.items.iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_seed_gives_a_reproducible_shuffle() {
+        let shuffled = |seed| {
+            let mut values: Vec<u32> = (0..50).collect();
+            shuffle(&mut values, &mut Rng::new(seed));
+            values
+        };
+        assert_eq!(shuffled(1), shuffled(1));
+        assert_ne!(shuffled(1), shuffled(2));
+        let mut sorted = shuffled(1);
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..50).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn samples_are_distinct_indices_in_range() {
+        let mut rng = Rng::new(4);
+        for (len, k) in [(0, 3), (5, 0), (5, 5), (10, 3), (100, 40), (3, 10)] {
+            let mut indices = sample_indices(len, k, &mut rng);
+            assert_eq!(indices.len(), k.min(len));
+            assert!(indices.iter().all(|&i| i < len));
+            indices.sort_unstable();
+            indices.dedup();
+            assert_eq!(indices.len(), k.min(len));
+        }
+        assert_eq!(
+            sample_indices(100, 10, &mut Rng::new(8)),
+            sample_indices(100, 10, &mut Rng::new(8))
+        );
+    }
+
+    #[test]
+    fn a_reservoir_keeps_k_distinct_elements_of_the_stream() {
+        let mut rng = Rng::new(6);
+        let mut kept = Vec::new();
+        for seen in 1..=100u64 {
+            match reservoir_slot(seen, 4, &mut rng) {
+                Some(slot) if slot == kept.len() => kept.push(seen),
+                Some(slot) => kept[slot] = seen,
+                None => {}
+            }
+        }
+        assert_eq!(kept.len(), 4);
+        // Some element past the first four must have replaced one of them
+        assert!(kept.iter().any(|&seen| seen > 4));
+        assert!(kept.iter().all(|&seen| (1..=100).contains(&seen)));
+        kept.sort_unstable();
+        kept.dedup();
+        assert_eq!(kept.len(), 4);
+    }
+}
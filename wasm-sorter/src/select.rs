@@ -0,0 +1,114 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::keys::KeyTable;
+
+/// A row of a `KeyTable`, ordered like a stable sort would order it: by the
+/// table's keys, then by input position.
+struct Row<'a> {
+    table: &'a KeyTable,
+    index: usize,
+}
+
+impl Ord for Row<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.table.compare_rows_stable(self.index, other.index)
+    }
+}
+
+impl PartialOrd for Row<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Row<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Row<'_> {}
+
+/// The indices of the first `k` rows in sorted order, found with a bounded
+/// max-heap in O(n log k).
+pub fn top_k_indices(table: &KeyTable, k: usize) -> Vec<usize> {
+    // `k` comes from the caller and may be far larger than the table
+    let k = k.min(table.len());
+    if k == 0 {
+        return Vec::new();
+    }
+
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for index in 0..table.len() {
+        let row = Row { table, index };
+        if heap.len() < k {
+            heap.push(row);
+        } else if heap.peek().is_some_and(|largest| row < *largest) {
+            heap.pop();
+            heap.push(row);
+        }
+    }
+
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|row| row.index)
+        .collect()
+}
+
+/// All row indices, with the first `k` in sorted order and the rest in no
+/// particular order after them. Uses introselect to split off the first `k`.
+pub fn partial_sort_indices(table: &KeyTable, k: usize) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..table.len()).collect();
+    let k = k.min(indices.len());
+    if k < indices.len() {
+        indices.select_nth_unstable_by(k, |&a, &b| table.compare_rows_stable(a, b));
+    }
+    indices[..k].sort_unstable_by(|&a, &b| table.compare_rows_stable(a, b));
+    indices
+}
+
+/// The index of the row that a stable sort would put at position `n`.
+pub fn select_nth_index(table: &KeyTable, n: usize) -> Option<usize> {
+    if n >= table.len() {
+        return None;
+    }
+    let mut indices: Vec<usize> = (0..table.len()).collect();
+    let (_, nth, _) = indices.select_nth_unstable_by(n, |&a, &b| table.compare_rows_stable(a, b));
+    Some(*nth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{KeyKind, KeyValue, SortKey};
+
+    fn table(values: &[f64]) -> KeyTable {
+        KeyTable::build_with(
+            vec![SortKey::identity(KeyKind::Number, false)],
+            values,
+            |_, &n| KeyValue::Number(n),
+        )
+    }
+
+    #[test]
+    fn top_k_clamps_k_to_the_table() {
+        let table = table(&[3.0, 1.0, 2.0, 1.0]);
+        assert_eq!(top_k_indices(&table, usize::MAX), [1, 3, 2, 0]);
+        assert_eq!(top_k_indices(&table, u32::MAX as usize), [1, 3, 2, 0]);
+        assert_eq!(top_k_indices(&table, 2), [1, 3]);
+        assert!(top_k_indices(&table, 0).is_empty());
+    }
+
+    #[test]
+    fn selection_matches_a_stable_sort() {
+        let table = table(&[5.0, 2.0, 2.0, 9.0, 0.0, 2.0]);
+        let sorted = [4, 1, 2, 5, 0, 3];
+        assert_eq!(&partial_sort_indices(&table, 3)[..3], &sorted[..3]);
+        assert_eq!(partial_sort_indices(&table, usize::MAX), sorted);
+        for (n, &index) in sorted.iter().enumerate() {
+            assert_eq!(select_nth_index(&table, n), Some(index));
+        }
+        assert_eq!(select_nth_index(&table, sorted.len()), None);
+    }
+}
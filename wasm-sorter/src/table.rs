@@ -0,0 +1,703 @@
+use std::cmp::Ordering;
+
+use js_sys::{
+    Array, ArrayBuffer, BigInt64Array, BigUint64Array, DataView, Float64Array, Object, Reflect,
+};
+use wasm_bindgen::prelude::*;
+
+use crate::algorithms::{self, SortAlgorithm, SortStats};
+use crate::js_error;
+use crate::keys::{self, KeyKind, SortKey};
+use crate::options::get_string;
+
+/// The largest magnitude, exclusive, of a number that converts to `i64`.
+const I64_LIMIT: f64 = 9_223_372_036_854_775_808.0;
+
+/// The cells of one column. Missing cells, and cells whose value had the
+/// wrong type when loaded, are `None`.
+#[derive(Clone)]
+enum ColumnData {
+    Float(Vec<Option<f64>>),
+    Int(Vec<Option<i64>>),
+    Text(Vec<Option<String>>),
+    Bool(Vec<Option<bool>>),
+}
+
+impl ColumnData {
+    /// An empty column of a schema type: `"f64"`, `"i64"`, `"string"` or
+    /// `"bool"`.
+    fn of_type(name: &str) -> Result<ColumnData, JsValue> {
+        match name {
+            "f64" => Ok(ColumnData::Float(Vec::new())),
+            "i64" => Ok(ColumnData::Int(Vec::new())),
+            "string" => Ok(ColumnData::Text(Vec::new())),
+            "bool" => Ok(ColumnData::Bool(Vec::new())),
+            other => Err(js_error(&format!(
+                "unknown column type '{}', expected 'f64', 'i64', 'string' or 'bool'",
+                other
+            ))),
+        }
+    }
+
+    /// An empty column for the type of a sample value, or `None` when the
+    /// value gives no type.
+    fn infer(value: &JsValue) -> Option<ColumnData> {
+        if value.as_f64().is_some() {
+            Some(ColumnData::Float(Vec::new()))
+        } else if value.is_bigint() {
+            Some(ColumnData::Int(Vec::new()))
+        } else if value.is_string() {
+            Some(ColumnData::Text(Vec::new()))
+        } else if value.as_bool().is_some() {
+            Some(ColumnData::Bool(Vec::new()))
+        } else {
+            None
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            ColumnData::Float(_) => "f64",
+            ColumnData::Int(_) => "i64",
+            ColumnData::Text(_) => "string",
+            ColumnData::Bool(_) => "bool",
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            ColumnData::Float(cells) => cells.len(),
+            ColumnData::Int(cells) => cells.len(),
+            ColumnData::Text(cells) => cells.len(),
+            ColumnData::Bool(cells) => cells.len(),
+        }
+    }
+
+    /// Appends a JS value. Integers load into `i64` columns from BigInts
+    /// or from numbers without a fractional part.
+    fn push(&mut self, value: &JsValue) {
+        match self {
+            ColumnData::Float(cells) => cells.push(value.as_f64()),
+            ColumnData::Int(cells) => cells.push(match value.as_f64() {
+                Some(n) if n.fract() == 0.0 && n.abs() < I64_LIMIT => Some(n as i64),
+                Some(_) => None,
+                None => i64::try_from(value.clone()).ok(),
+            }),
+            ColumnData::Text(cells) => cells.push(value.as_string()),
+            ColumnData::Bool(cells) => cells.push(value.as_bool()),
+        }
+    }
+
+    /// An `i64` column from the values of a `BigUint64Array`, or the first
+    /// value that does not fit in an `i64`.
+    fn from_unsigned(values: &[u64]) -> Result<ColumnData, u64> {
+        values
+            .iter()
+            .map(|&value| i64::try_from(value).map(Some).map_err(|_| value))
+            .collect::<Result<_, _>>()
+            .map(ColumnData::Int)
+    }
+
+    /// A new column with the cells at `rows`, in that order.
+    fn take(&self, rows: &[usize]) -> ColumnData {
+        fn gather<T: Clone>(cells: &[Option<T>], rows: &[usize]) -> Vec<Option<T>> {
+            rows.iter().map(|&row| cells[row].clone()).collect()
+        }
+        match self {
+            ColumnData::Float(cells) => ColumnData::Float(gather(cells, rows)),
+            ColumnData::Int(cells) => ColumnData::Int(gather(cells, rows)),
+            ColumnData::Text(cells) => ColumnData::Text(gather(cells, rows)),
+            ColumnData::Bool(cells) => ColumnData::Bool(gather(cells, rows)),
+        }
+    }
+
+    fn is_missing(&self, row: usize) -> bool {
+        match self {
+            ColumnData::Float(cells) => cells[row].is_none(),
+            ColumnData::Int(cells) => cells[row].is_none(),
+            ColumnData::Text(cells) => cells[row].is_none(),
+            ColumnData::Bool(cells) => cells[row].is_none(),
+        }
+    }
+
+    /// Whether a key of type `kind` can sort this column. A float column
+    /// of timestamps sorts as dates, and `i64` cells compare exactly
+    /// whether the key says number or BigInt.
+    fn accepts(&self, kind: KeyKind) -> bool {
+        kind == KeyKind::Mixed
+            || match self {
+                ColumnData::Float(_) => matches!(kind, KeyKind::Number | KeyKind::Date),
+                ColumnData::Int(_) => matches!(kind, KeyKind::Number | KeyKind::BigInt),
+                ColumnData::Text(_) => kind == KeyKind::String,
+                ColumnData::Bool(_) => kind == KeyKind::Boolean,
+            }
+    }
+
+    /// Compares two cells in place, as `key` would compare their values.
+    fn compare_rows(&self, key: &SortKey, a: usize, b: usize) -> Ordering {
+        let directed = |ordering: Ordering| {
+            if key.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        };
+        match self {
+            ColumnData::Float(cells) => key.compare_cells(cells[a], cells[b], |x, y| {
+                key.numbers.compare(x, y, key.descending)
+            }),
+            ColumnData::Int(cells) => {
+                key.compare_cells(cells[a], cells[b], |x, y| directed(x.cmp(&y)))
+            }
+            ColumnData::Text(cells) => {
+                key.compare_cells(cells[a].as_deref(), cells[b].as_deref(), |x, y| {
+                    directed(key.order.compare(x, y))
+                })
+            }
+            ColumnData::Bool(cells) => {
+                key.compare_cells(cells[a], cells[b], |x, y| directed(x.cmp(&y)))
+            }
+        }
+    }
+
+    /// The cell as a JS value: `i64` cells become BigInts and missing cells
+    /// `null`.
+    fn get(&self, row: usize) -> JsValue {
+        let value = match self {
+            ColumnData::Float(cells) => cells[row].map(JsValue::from_f64),
+            ColumnData::Int(cells) => cells[row].map(JsValue::from),
+            ColumnData::Text(cells) => cells[row].as_deref().map(JsValue::from_str),
+            ColumnData::Bool(cells) => cells[row].map(JsValue::from_bool),
+        };
+        value.unwrap_or(JsValue::NULL)
+    }
+
+    /// The whole column: a typed array for a numeric column without missing
+    /// cells, a plain array otherwise.
+    fn to_js(&self) -> JsValue {
+        match self {
+            ColumnData::Float(cells) => {
+                if let Some(values) = cells.iter().copied().collect::<Option<Vec<f64>>>() {
+                    return Float64Array::from(&values[..]).into();
+                }
+            }
+            ColumnData::Int(cells) => {
+                if let Some(values) = cells.iter().copied().collect::<Option<Vec<i64>>>() {
+                    return BigInt64Array::from(&values[..]).into();
+                }
+            }
+            _ => {}
+        }
+        (0..self.len())
+            .map(|row| self.get(row))
+            .collect::<Array>()
+            .into()
+    }
+}
+
+struct Column {
+    name: String,
+    data: ColumnData,
+}
+
+/// Filter operators. `contains`, `startsWith` and `endsWith` are for
+/// string columns only.
+#[derive(Clone, Copy)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+impl Op {
+    fn parse(name: &str) -> Result<Op, JsValue> {
+        match name {
+            "==" => Ok(Op::Eq),
+            "!=" => Ok(Op::Ne),
+            "<" => Ok(Op::Lt),
+            "<=" => Ok(Op::Le),
+            ">" => Ok(Op::Gt),
+            ">=" => Ok(Op::Ge),
+            "contains" => Ok(Op::Contains),
+            "startsWith" => Ok(Op::StartsWith),
+            "endsWith" => Ok(Op::EndsWith),
+            other => Err(js_error(&format!(
+                "unknown filter operator '{}', expected '==', '!=', '<', '<=', '>', '>=', \
+                 'contains', 'startsWith' or 'endsWith'",
+                other
+            ))),
+        }
+    }
+
+    fn is_text_only(self) -> bool {
+        matches!(self, Op::Contains | Op::StartsWith | Op::EndsWith)
+    }
+
+    fn accepts(self, ordering: Ordering) -> bool {
+        match self {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+            Op::Contains | Op::StartsWith | Op::EndsWith => false,
+        }
+    }
+}
+
+/// One filter condition, with its value converted to the column's type.
+struct Condition {
+    column: usize,
+    op: Op,
+    /// A one-cell column holding the value, so that it is typed and
+    /// converted exactly like the cells it is compared with.
+    value: ColumnData,
+}
+
+impl Condition {
+    /// Tests one row. Missing cells match no condition, not even `!=`.
+    fn matches(&self, data: &ColumnData, row: usize) -> bool {
+        let op = self.op;
+        match (data, &self.value) {
+            (ColumnData::Float(cells), ColumnData::Float(value)) => match (cells[row], value[0]) {
+                (Some(x), Some(y)) => x.partial_cmp(&y).is_some_and(|o| op.accepts(o)),
+                _ => false,
+            },
+            (ColumnData::Int(cells), ColumnData::Int(value)) => match (cells[row], value[0]) {
+                (Some(x), Some(y)) => op.accepts(x.cmp(&y)),
+                _ => false,
+            },
+            (ColumnData::Text(cells), ColumnData::Text(value)) => match (&cells[row], &value[0]) {
+                (Some(x), Some(y)) => match op {
+                    Op::Contains => x.contains(y.as_str()),
+                    Op::StartsWith => x.starts_with(y.as_str()),
+                    Op::EndsWith => x.ends_with(y.as_str()),
+                    _ => op.accepts(x.cmp(y)),
+                },
+                _ => false,
+            },
+            (ColumnData::Bool(cells), ColumnData::Bool(value)) => match (cells[row], value[0]) {
+                (Some(x), Some(y)) => op.accepts(x.cmp(&y)),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+/// A table of typed columns held in WASM memory (struct-of-arrays), for
+/// sorting, filtering and projecting tabular data without a JS object per
+/// row. Every operation returns a new table, so calls chain:
+/// `table.filter(conditions).sort("albumId, id").select(["id", "title"]).to_rows()`.
+///
+/// Columns are `f64`, `i64`, `string` or `bool`. A cell can be missing,
+/// which is how absent properties, `null`, and values of the wrong type
+/// load.
+#[wasm_bindgen]
+pub struct Table {
+    columns: Vec<Column>,
+    len: usize,
+}
+
+impl Table {
+    fn column_index(&self, name: &str) -> Result<usize, JsValue> {
+        self.columns
+            .iter()
+            .position(|column| column.name == name)
+            .ok_or_else(|| js_error(&format!("unknown column '{}'", name)))
+    }
+
+    /// A new table with the rows at `rows`, in that order.
+    fn take(&self, rows: &[usize]) -> Table {
+        Table {
+            columns: self
+                .columns
+                .iter()
+                .map(|column| Column {
+                    name: column.name.clone(),
+                    data: column.data.take(rows),
+                })
+                .collect(),
+            len: rows.len(),
+        }
+    }
+
+    fn parse_condition(&self, entry: &JsValue) -> Result<Condition, JsValue> {
+        let name = get_string(entry, "column")?
+            .ok_or_else(|| js_error("filter condition is missing a 'column'"))?;
+        let column = self.column_index(&name)?;
+        let op = Op::parse(&get_string(entry, "op")?.unwrap_or_else(|| "==".to_string()))?;
+
+        let data = &self.columns[column].data;
+        if op.is_text_only() && !matches!(data, ColumnData::Text(_)) {
+            return Err(js_error(&format!(
+                "filter operator on column '{}' needs a string column",
+                name
+            )));
+        }
+        let mut value = ColumnData::of_type(data.type_name())?;
+        value.push(&Reflect::get(entry, &JsValue::from_str("value"))?);
+        if value.is_missing(0) {
+            return Err(js_error(&format!(
+                "filter value for column '{}' must be of type {}",
+                name,
+                data.type_name()
+            )));
+        }
+
+        Ok(Condition { column, op, value })
+    }
+}
+
+#[wasm_bindgen]
+impl Table {
+    /// Loads a table from an array of objects. `schema` maps column names
+    /// to types, e.g. `{ id: "i64", title: "string" }`, and picks the
+    /// columns in that order. Without one, the columns are the first row's
+    /// properties, typed by their first value that is not missing.
+    pub fn from_rows(rows: &Array, schema: &JsValue) -> Result<Table, JsValue> {
+        let mut columns = Vec::new();
+        if schema.is_undefined() || schema.is_null() {
+            let first = rows.get(0);
+            if first.is_object() {
+                for name in Object::keys(first.unchecked_ref::<Object>()).iter() {
+                    let name = name.as_string().unwrap_or_default();
+                    let key = JsValue::from_str(&name);
+                    let data = rows
+                        .iter()
+                        .map(|row| Reflect::get(&row, &key).unwrap_or(JsValue::UNDEFINED))
+                        .find_map(|value| ColumnData::infer(&value))
+                        .ok_or_else(|| {
+                            js_error(&format!(
+                                "cannot infer the type of column '{}', pass a schema",
+                                name
+                            ))
+                        })?;
+                    columns.push(Column { name, data });
+                }
+            }
+        } else {
+            for entry in Object::entries(schema.unchecked_ref()).iter() {
+                let entry = Array::from(&entry);
+                let name = entry.get(0).as_string().unwrap_or_default();
+                let kind = entry.get(1).as_string().ok_or_else(|| {
+                    js_error(&format!("type of column '{}' must be a string", name))
+                })?;
+                columns.push(Column {
+                    name,
+                    data: ColumnData::of_type(&kind)?,
+                });
+            }
+        }
+
+        for column in &mut columns {
+            let key = JsValue::from_str(&column.name);
+            for row in rows.iter() {
+                let value = if row.is_object() {
+                    Reflect::get(&row, &key)?
+                } else {
+                    JsValue::UNDEFINED
+                };
+                column.data.push(&value);
+            }
+        }
+
+        Ok(Table {
+            columns,
+            len: rows.length() as usize,
+        })
+    }
+
+    /// Loads a table from an object of columns, e.g.
+    /// `{ id: new BigInt64Array(ids), score: new Float64Array(scores), title: titles }`.
+    /// Numeric typed arrays load as `f64` columns, `BigInt64Array`s and
+    /// `BigUint64Array`s as `i64` columns and plain arrays by their first
+    /// value that is not missing. A `BigUint64Array` with a value above
+    /// `i64::MAX` is an error.
+    /// `schema` can set the type of any column. All columns must have the
+    /// same length.
+    pub fn from_columns(columns: &Object, schema: &JsValue) -> Result<Table, JsValue> {
+        let mut loaded = Vec::new();
+        let mut len = None;
+        for name in Object::keys(columns).iter() {
+            let name = name.as_string().unwrap_or_default();
+            let source = Reflect::get(columns, &JsValue::from_str(&name))?;
+            let kind = get_string(schema, &name)?;
+
+            let typed = ArrayBuffer::is_view(&source) && !source.is_instance_of::<DataView>();
+            if !typed && !Array::is_array(&source) {
+                return Err(js_error(&format!(
+                    "column '{}' must be an array or a typed array",
+                    name
+                )));
+            }
+            let big = source.is_instance_of::<BigInt64Array>()
+                || source.is_instance_of::<BigUint64Array>();
+
+            let data = if let (None, Some(values)) = (&kind, source.dyn_ref::<BigInt64Array>()) {
+                ColumnData::Int(values.to_vec().into_iter().map(Some).collect())
+            } else if let (None | Some("i64"), Some(values)) =
+                (kind.as_deref(), source.dyn_ref::<BigUint64Array>())
+            {
+                // Values past `i64::MAX` would otherwise load as missing
+                ColumnData::from_unsigned(&values.to_vec()).map_err(|value| {
+                    js_error(&format!(
+                        "column '{}' has the value {}, which does not fit in an i64 column",
+                        name, value
+                    ))
+                })?
+            } else if kind.is_none() && typed && !big {
+                let values = Float64Array::new(&source).to_vec();
+                ColumnData::Float(values.into_iter().map(Some).collect())
+            } else {
+                // Converted value by value, the way rows are
+                let values = Array::from(&source);
+                let mut data = match &kind {
+                    Some(kind) => ColumnData::of_type(kind)?,
+                    None => values
+                        .iter()
+                        .find_map(|value| ColumnData::infer(&value))
+                        .ok_or_else(|| {
+                            js_error(&format!(
+                                "cannot infer the type of column '{}', pass a schema",
+                                name
+                            ))
+                        })?,
+                };
+                for value in values.iter() {
+                    data.push(&value);
+                }
+                data
+            };
+
+            match len {
+                None => len = Some(data.len()),
+                Some(expected) if expected != data.len() => {
+                    return Err(js_error(&format!(
+                        "column '{}' has {} values where earlier columns have {}",
+                        name,
+                        data.len(),
+                        expected
+                    )))
+                }
+                Some(_) => {}
+            }
+            loaded.push(Column { name, data });
+        }
+
+        Ok(Table {
+            columns: loaded,
+            len: len.unwrap_or(0),
+        })
+    }
+
+    /// The number of rows.
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> u32 {
+        self.len as u32
+    }
+
+    pub fn column_names(&self) -> Array {
+        self.columns
+            .iter()
+            .map(|column| JsValue::from_str(&column.name))
+            .collect()
+    }
+
+    /// The column types, as a schema object accepted by `from_rows`.
+    pub fn schema(&self) -> Result<Object, JsValue> {
+        let schema = Object::new();
+        for column in &self.columns {
+            Reflect::set(
+                &schema,
+                &JsValue::from_str(&column.name),
+                &JsValue::from_str(column.data.type_name()),
+            )?;
+        }
+        Ok(schema)
+    }
+
+    /// Returns a column: a `Float64Array` or `BigInt64Array` for numeric
+    /// columns without missing cells, otherwise an array with `null` for
+    /// missing cells.
+    pub fn column(&self, name: &str) -> Result<JsValue, JsValue> {
+        Ok(self.columns[self.column_index(name)?].data.to_js())
+    }
+
+    /// Returns row `index` as an object, or `undefined` if the index is out
+    /// of range. `i64` cells become BigInts.
+    pub fn row(&self, index: u32) -> Result<JsValue, JsValue> {
+        let index = index as usize;
+        if index >= self.len {
+            return Ok(JsValue::UNDEFINED);
+        }
+        let row = Object::new();
+        for column in &self.columns {
+            Reflect::set(
+                &row,
+                &JsValue::from_str(&column.name),
+                &column.data.get(index),
+            )?;
+        }
+        Ok(row.into())
+    }
+
+    /// Returns every row as an object, like `row`.
+    pub fn to_rows(&self) -> Result<Array, JsValue> {
+        (0..self.len as u32).map(|index| self.row(index)).collect()
+    }
+
+    /// Returns the table sorted by `spec`, which takes the same forms as the
+    /// `sort_by_keys` specification with column names as paths. Key types
+    /// come from the columns, and a `type` that does not fit its column is
+    /// an error; `collation`, `nulls` and the float ordering options apply
+    /// as for `sort_by_keys`. `options` takes the sort options of
+    /// `sort_by_keys`.
+    pub fn sort(&self, spec: &JsValue, options: &JsValue) -> Result<Table, JsValue> {
+        let keys = keys::parse_spec(spec)?;
+        let mut columns = Vec::new();
+        for key in &keys {
+            let name = key.path.join(".");
+            let data = &self.columns[self.column_index(&name)?].data;
+            if key.typed && !data.accepts(key.kind) {
+                return Err(js_error(&format!(
+                    "column '{}' has type '{}', which a '{}' key cannot sort",
+                    name,
+                    data.type_name(),
+                    key.kind.name()
+                )));
+            }
+            columns.push(data);
+        }
+
+        let algorithm = SortAlgorithm::comparison_from_options(options)?;
+        let stable = algorithms::stable_from_options(options, algorithm, true)?;
+        let mut stats = SortStats::new();
+        let mut indices: Vec<usize> = (0..self.len).collect();
+        algorithms::sort_by(&mut indices, algorithm, stable, &mut stats, |&a, &b| {
+            keys.iter()
+                .zip(&columns)
+                .map(|(key, data)| data.compare_rows(key, a, b))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        stats.report(options)?;
+
+        Ok(self.take(&indices))
+    }
+
+    /// Returns the rows matching every condition. A condition is an object
+    /// `{ column, op, value }` with `op` one of `==` (the default), `!=`,
+    /// `<`, `<=`, `>`, `>=`, and for strings `contains`, `startsWith` and
+    /// `endsWith`. `conditions` is one condition or an array of them.
+    pub fn filter(&self, conditions: &JsValue) -> Result<Table, JsValue> {
+        let conditions = if Array::is_array(conditions) {
+            Array::from(conditions)
+                .iter()
+                .map(|entry| self.parse_condition(&entry))
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            vec![self.parse_condition(conditions)?]
+        };
+
+        let rows: Vec<usize> = (0..self.len)
+            .filter(|&row| {
+                conditions
+                    .iter()
+                    .all(|condition| condition.matches(&self.columns[condition.column].data, row))
+            })
+            .collect();
+        Ok(self.take(&rows))
+    }
+
+    /// Returns a table with the named columns, in the given order.
+    pub fn select(&self, names: &Array) -> Result<Table, JsValue> {
+        let mut columns = Vec::new();
+        for name in names.iter() {
+            let name = name
+                .as_string()
+                .ok_or_else(|| js_error("column names must be strings"))?;
+            let index = self.column_index(&name)?;
+            columns.push(Column {
+                name,
+                data: self.columns[index].data.clone(),
+            });
+        }
+        Ok(Table {
+            columns,
+            len: self.len,
+        })
+    }
+
+    /// Returns up to `count` rows starting at row `start`.
+    pub fn slice(&self, start: u32, count: u32) -> Table {
+        let start = (start as usize).min(self.len);
+        let end = start.saturating_add(count as usize).min(self.len);
+        self.take(&(start..end).collect::<Vec<_>>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(data: &ColumnData, key: &SortKey) -> Vec<usize> {
+        let mut rows: Vec<usize> = (0..data.len()).collect();
+        rows.sort_by(|&a, &b| data.compare_rows(key, a, b));
+        rows
+    }
+
+    #[test]
+    fn int_cells_compare_exactly_in_place() {
+        let data = ColumnData::Int(vec![Some(i64::MAX), None, Some(i64::MAX - 1), Some(-3)]);
+        let mut key = SortKey::identity(KeyKind::BigInt, false);
+        assert_eq!(sorted(&data, &key), [3, 2, 0, 1]);
+        key.descending = true;
+        assert_eq!(sorted(&data, &key), [0, 2, 3, 1]);
+        key.nulls_first = true;
+        assert_eq!(sorted(&data, &key), [1, 0, 2, 3]);
+    }
+
+    #[test]
+    fn cells_follow_the_key_orderings() {
+        let floats = ColumnData::Float(vec![Some(1.0), Some(f64::NAN), None, Some(-0.0)]);
+        let key = SortKey::identity(KeyKind::Number, true);
+        assert_eq!(sorted(&floats, &key), [0, 3, 1, 2]);
+
+        let text = ColumnData::Text(vec![Some("b".into()), None, Some("B".into())]);
+        let key = SortKey::identity(KeyKind::String, false);
+        assert_eq!(sorted(&text, &key), [2, 0, 1]);
+    }
+
+    #[test]
+    fn keys_must_fit_the_column_type() {
+        let text = ColumnData::Text(Vec::new());
+        assert!(text.accepts(KeyKind::String));
+        assert!(text.accepts(KeyKind::Mixed));
+        assert!(!text.accepts(KeyKind::Number));
+        let ints = ColumnData::Int(Vec::new());
+        assert!(ints.accepts(KeyKind::BigInt));
+        assert!(!ints.accepts(KeyKind::Boolean));
+        assert!(ColumnData::Float(Vec::new()).accepts(KeyKind::Date));
+    }
+
+    #[test]
+    fn unsigned_columns_must_fit_in_i64() {
+        let max = i64::MAX as u64;
+        let data = ColumnData::from_unsigned(&[0, 7, max]);
+        assert!(
+            matches!(data, Ok(ColumnData::Int(cells)) if cells == [Some(0), Some(7), Some(i64::MAX)])
+        );
+        assert!(matches!(
+            ColumnData::from_unsigned(&[1, max + 1, u64::MAX]),
+            Err(value) if value == max + 1
+        ));
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+
+use js_sys::{Array, Function, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+use crate::options::get_number;
+use crate::rng::Rng;
+
+/// Default number of pairs and of triples checked.
+const DEFAULT_BUDGET: usize = 1000;
+/// Default number of violations listed in the report. All are counted.
+const DEFAULT_MAX_VIOLATIONS: usize = 20;
+
+const KINDS: [&str; 5] = [
+    "reflexivity",
+    "antisymmetry",
+    "transitivity",
+    "nonNumeric",
+    "exception",
+];
+
+/// A comparator result, reduced to its sign the way `sort_array` reads it.
+#[derive(Clone)]
+struct Outcome {
+    raw: JsValue,
+    /// `None` when the comparator threw.
+    sign: Option<i8>,
+}
+
+struct Violation {
+    kind: &'static str,
+    indices: Vec<usize>,
+    results: Vec<JsValue>,
+    message: String,
+}
+
+/// Checks that a comparator defines a consistent order on sampled elements.
+pub struct Checker<'a> {
+    items: &'a [JsValue],
+    predicate: &'a Function,
+    memo: HashMap<(usize, usize), Outcome>,
+    counts: [u32; KINDS.len()],
+    violations: Vec<Violation>,
+    max_violations: usize,
+}
+
+impl<'a> Checker<'a> {
+    pub fn new(items: &'a [JsValue], predicate: &'a Function) -> Checker<'a> {
+        Checker {
+            items,
+            predicate,
+            memo: HashMap::new(),
+            counts: [0; KINDS.len()],
+            violations: Vec::new(),
+            max_violations: DEFAULT_MAX_VIOLATIONS,
+        }
+    }
+
+    fn record(
+        &mut self,
+        kind: &'static str,
+        indices: &[usize],
+        results: &[&Outcome],
+        message: String,
+    ) {
+        let slot = KINDS.iter().position(|k| *k == kind).unwrap_or(0);
+        self.counts[slot] += 1;
+        if self.violations.len() < self.max_violations {
+            self.violations.push(Violation {
+                kind,
+                indices: indices.to_vec(),
+                results: results.iter().map(|outcome| outcome.raw.clone()).collect(),
+                message,
+            });
+        }
+    }
+
+    /// Calls the comparator once per ordered pair, recording results that
+    /// are not numbers and exceptions the first time they happen.
+    fn compare(&mut self, i: usize, j: usize) -> Outcome {
+        if let Some(outcome) = self.memo.get(&(i, j)) {
+            return outcome.clone();
+        }
+
+        let outcome = match self
+            .predicate
+            .call2(&JsValue::null(), &self.items[i], &self.items[j])
+        {
+            Ok(raw) => {
+                let numeric = raw.as_f64().is_some_and(|n| !n.is_nan());
+                let number = crate::to_number(&raw).unwrap_or(f64::NAN);
+                let outcome = Outcome {
+                    sign: Some(if number < 0.0 {
+                        -1
+                    } else if number > 0.0 {
+                        1
+                    } else {
+                        0
+                    }),
+                    raw,
+                };
+                if !numeric {
+                    self.record(
+                        "nonNumeric",
+                        &[i, j],
+                        &[&outcome],
+                        format!(
+                            "compare({}, {}) returned {:?} instead of a number",
+                            i, j, outcome.raw
+                        ),
+                    );
+                }
+                outcome
+            }
+            Err(err) => {
+                let outcome = Outcome {
+                    raw: err,
+                    sign: None,
+                };
+                self.record(
+                    "exception",
+                    &[i, j],
+                    &[&outcome],
+                    format!("compare({}, {}) threw", i, j),
+                );
+                outcome
+            }
+        };
+        self.memo.insert((i, j), outcome.clone());
+        outcome
+    }
+
+    /// An element must compare equal to itself.
+    fn check_reflexive(&mut self, i: usize) {
+        let outcome = self.compare(i, i);
+        if outcome.sign.is_some_and(|sign| sign != 0) {
+            self.record(
+                "reflexivity",
+                &[i],
+                &[&outcome],
+                format!("compare({0}, {0}) is not 0", i),
+            );
+        }
+    }
+
+    /// Swapping the arguments must flip the sign of the result.
+    fn check_antisymmetric(&mut self, i: usize, j: usize) {
+        let (forward, backward) = (self.compare(i, j), self.compare(j, i));
+        if let (Some(x), Some(y)) = (forward.sign, backward.sign) {
+            if x != -y {
+                self.record(
+                    "antisymmetry",
+                    &[i, j],
+                    &[&forward, &backward],
+                    format!(
+                        "compare({0}, {1}) and compare({1}, {0}) do not have opposite signs",
+                        i, j
+                    ),
+                );
+            }
+        }
+    }
+
+    /// `i <= j` and `j <= k` must give `i <= k`, with equality carried
+    /// through, for every ordering of the three elements.
+    fn check_transitive(&mut self, triple: [usize; 3]) {
+        const PERMUTATIONS: [[usize; 3]; 6] = [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ];
+        for permutation in PERMUTATIONS {
+            let [i, j, k] = permutation.map(|p| triple[p]);
+            let (first, second, outer) =
+                (self.compare(i, j), self.compare(j, k), self.compare(i, k));
+            let (Some(x), Some(y), Some(z)) = (first.sign, second.sign, outer.sign) else {
+                continue;
+            };
+            let implied = if x == y || y == 0 {
+                Some(x)
+            } else if x == 0 {
+                Some(y)
+            } else {
+                None
+            };
+            if implied.is_some_and(|implied| implied != z) {
+                self.record(
+                    "transitivity",
+                    &[i, j, k],
+                    &[&first, &second, &outer],
+                    format!(
+                        "compare({0}, {1}) and compare({1}, {2}) imply the sign of compare({0}, {2}), which differs",
+                        i, j, k
+                    ),
+                );
+                // One report per triple is enough
+                return;
+            }
+        }
+    }
+
+    /// Runs the checks and builds the report. Pairs and triples are checked
+    /// exhaustively when there are few enough, otherwise sampled at random.
+    pub fn run(mut self, options: &JsValue) -> Result<Object, JsValue> {
+        let budget = |name| -> Result<usize, JsValue> {
+            Ok(get_number(options, name)?.map_or(DEFAULT_BUDGET, |n| n.max(0.0) as usize))
+        };
+        let pair_budget = budget("pairs")?;
+        let triple_budget = budget("triples")?;
+        if let Some(max) = get_number(options, "maxViolations")? {
+            self.max_violations = max.max(0.0) as usize;
+        }
+        let (mut rng, seed) = Rng::from_options(options)?;
+        let n = self.items.len();
+
+        for i in 0..n.min(pair_budget) {
+            let i = if n <= pair_budget {
+                i
+            } else {
+                rng.below(n as u64) as usize
+            };
+            self.check_reflexive(i);
+        }
+
+        let mut checked_pairs = 0;
+        if n >= 2 {
+            // Saturates where `usize` is 32 bits and n exceeds 65536
+            if n.saturating_mul(n - 1) / 2 <= pair_budget {
+                for i in 0..n {
+                    for j in i + 1..n {
+                        self.check_antisymmetric(i, j);
+                        checked_pairs += 1;
+                    }
+                }
+            } else {
+                for _ in 0..pair_budget {
+                    let i = rng.below(n as u64) as usize;
+                    let j = (i + 1 + rng.below(n as u64 - 1) as usize) % n;
+                    self.check_antisymmetric(i, j);
+                    checked_pairs += 1;
+                }
+            }
+        }
+
+        let mut checked_triples = 0;
+        if n >= 3 {
+            if n.saturating_mul(n - 1).saturating_mul(n - 2) / 6 <= triple_budget {
+                for i in 0..n {
+                    for j in i + 1..n {
+                        for k in j + 1..n {
+                            self.check_transitive([i, j, k]);
+                            checked_triples += 1;
+                        }
+                    }
+                }
+            } else {
+                for _ in 0..triple_budget {
+                    let i = rng.below(n as u64) as usize;
+                    let j = (i + 1 + rng.below(n as u64 - 1) as usize) % n;
+                    let mut k = rng.below(n as u64 - 2) as usize;
+                    // Skip over i and j, in increasing order
+                    for taken in [i.min(j), i.max(j)] {
+                        if k >= taken {
+                            k += 1;
+                        }
+                    }
+                    self.check_transitive([i, j, k]);
+                    checked_triples += 1;
+                }
+            }
+        }
+
+        self.report(seed, checked_pairs, checked_triples)
+    }
+
+    fn report(
+        &self,
+        seed: u64,
+        checked_pairs: u32,
+        checked_triples: u32,
+    ) -> Result<Object, JsValue> {
+        let set = |target: &Object, name: &str, value: &JsValue| {
+            Reflect::set(target, &JsValue::from_str(name), value).map(|_| ())
+        };
+
+        let counts = Object::new();
+        for (kind, count) in KINDS.iter().zip(self.counts) {
+            set(&counts, kind, &JsValue::from(count))?;
+        }
+
+        let violations = Array::new();
+        for violation in &self.violations {
+            let entry = Object::new();
+            set(&entry, "kind", &JsValue::from_str(violation.kind))?;
+            let indices: Array = violation
+                .indices
+                .iter()
+                .map(|&index| JsValue::from(index as u32))
+                .collect();
+            set(&entry, "indices", &indices)?;
+            let results: Array = violation.results.iter().collect();
+            set(&entry, "results", &results)?;
+            set(&entry, "message", &JsValue::from_str(&violation.message))?;
+            violations.push(&entry);
+        }
+
+        let report = Object::new();
+        set(
+            &report,
+            "valid",
+            &JsValue::from_bool(self.counts.iter().all(|&count| count == 0)),
+        )?;
+        set(&report, "seed", &JsValue::from_f64(seed as f64))?;
+        set(&report, "checkedPairs", &JsValue::from(checked_pairs))?;
+        set(&report, "checkedTriples", &JsValue::from(checked_triples))?;
+        set(&report, "counts", &counts)?;
+        set(&report, "violations", &violations)?;
+        Ok(report)
+    }
+}